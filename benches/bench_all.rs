@@ -20,12 +20,12 @@ fn bench_dfa(c: &mut Criterion) {
 
     c.bench_function("dfa `abc`", |b| b.iter(|| searcher.lookup("abc")));
     c.bench_function("dfa `abcdef`", |b| b.iter(|| searcher.lookup("abcdef")));
-    // c.bench_function("dfa `abcdefghijkl`", |b| {
-    //     b.iter(|| searcher.lookup("abcdefghijkl"))
-    // });
-    // c.bench_function("dfa `abcdefghijklmnopqrstuvwx`", |b| {
-    //     b.iter(|| searcher.lookup("abcdefghijklmnopqrstuvwx"))
-    // });
+    c.bench_function("dfa `abcdefghijkl`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijkl"))
+    });
+    c.bench_function("dfa `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijklmnopqrstuvwx"))
+    });
 }
 
 fn bench_trie(c: &mut Criterion) {
@@ -49,6 +49,9 @@ fn bench_exp(c: &mut Criterion) {
     c.bench_function("exp `abcdefghijkl`", |b| {
         b.iter(|| searcher.lookup("abcdefghijkl"))
     });
+    c.bench_function("exp `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijklmnopqrstuvwx"))
+    });
 }
 
 fn bench_simple(c: &mut Criterion) {
@@ -64,12 +67,135 @@ fn bench_simple(c: &mut Criterion) {
     });
 }
 
+fn bench_columnar(c: &mut Criterion) {
+    let searcher = ColumnarSearcher::default();
+
+    c.bench_function("columnar `abc`", |b| b.iter(|| searcher.lookup("abc")));
+    c.bench_function("columnar `abcdef`", |b| {
+        b.iter(|| searcher.lookup("abcdef"))
+    });
+    c.bench_function("columnar `abcdefghijkl`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijkl"))
+    });
+    c.bench_function("columnar `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijklmnopqrstuvwx"))
+    });
+}
+
+fn bench_fst(c: &mut Criterion) {
+    let searcher = FstSearcher::default();
+
+    c.bench_function("fst `abc`", |b| b.iter(|| searcher.lookup("abc")));
+    c.bench_function("fst `abcdef`", |b| b.iter(|| searcher.lookup("abcdef")));
+    c.bench_function("fst `abcdefghijkl`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijkl"))
+    });
+    c.bench_function("fst `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| searcher.lookup("abcdefghijklmnopqrstuvwx"))
+    });
+}
+
+fn bench_iter(c: &mut Criterion) {
+    let dag = DAGSearcher::default();
+    let simple = SimpleSearcher::default();
+    let trie = TrieSearcher::default();
+
+    c.bench_function("dag iter first 20 `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| dag.lookup_iter("abcdefghijklmnopqrstuvwx").take(20).count())
+    });
+    c.bench_function("simple iter first 20 `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| {
+            simple
+                .lookup_iter("abcdefghijklmnopqrstuvwx")
+                .take(20)
+                .count()
+        })
+    });
+    c.bench_function("trie iter first 20 `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| {
+            trie.lookup_iter("abcdefghijklmnopqrstuvwx")
+                .take(20)
+                .count()
+        })
+    });
+}
+
+fn bench_top_k(c: &mut Criterion) {
+    let dag = DAGSearcher::default();
+    let simple = SimpleSearcher::default();
+    let trie = TrieSearcher::default();
+
+    c.bench_function("dag top 20 `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| dag.lookup_top_k("abcdefghijklmnopqrstuvwx", 20, Ranking::Frequency))
+    });
+    c.bench_function("simple top 20 `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| simple.lookup_top_k("abcdefghijklmnopqrstuvwx", 20, Ranking::Frequency))
+    });
+    c.bench_function("trie top 20 `abcdefghijklmnopqrstuvwx`", |b| {
+        b.iter(|| trie.lookup_top_k("abcdefghijklmnopqrstuvwx", 20, Ranking::Frequency))
+    });
+}
+
+fn bench_filter(c: &mut Criterion) {
+    let dag = DAGSearcher::default();
+    let simple = SimpleSearcher::default();
+    let trie = TrieSearcher::default();
+    let filter = Filter::new("s_3");
+
+    c.bench_function("dag post-filter `abcdefghijklmnopqrstuvwx` `s_3`", |b| {
+        b.iter(|| {
+            dag.lookup_iter("abcdefghijklmnopqrstuvwx")
+                .filter(|w| filter.matches(w))
+                .count()
+        })
+    });
+    c.bench_function("dag filter `abcdefghijklmnopqrstuvwx` `s_3`", |b| {
+        b.iter(|| {
+            dag.lookup_iter_filter("abcdefghijklmnopqrstuvwx", &filter)
+                .count()
+        })
+    });
+    c.bench_function("simple post-filter `abcdefghijklmnopqrstuvwx` `s_3`", |b| {
+        b.iter(|| {
+            simple
+                .lookup_iter("abcdefghijklmnopqrstuvwx")
+                .filter(|w| filter.matches(w))
+                .count()
+        })
+    });
+    c.bench_function("simple filter `abcdefghijklmnopqrstuvwx` `s_3`", |b| {
+        b.iter(|| {
+            simple
+                .lookup_iter_filter("abcdefghijklmnopqrstuvwx", &filter)
+                .count()
+        })
+    });
+    c.bench_function("trie post-filter `abcdefghijklmnopqrstuvwx` `s_3`", |b| {
+        b.iter(|| {
+            trie.lookup_iter("abcdefghijklmnopqrstuvwx")
+                .filter(|w| filter.matches(w))
+                .count()
+        })
+    });
+    c.bench_function("trie filter `abcdefghijklmnopqrstuvwx` `s_3`", |b| {
+        b.iter(|| {
+            trie.lookup_iter_filter("abcdefghijklmnopqrstuvwx", &filter)
+                .count()
+        })
+    });
+}
+
 criterion_group!(
     benches,
     bench_dag,
     bench_dfa,
     bench_trie,
     bench_exp,
-    bench_simple
+    bench_simple,
+    bench_columnar,
+    bench_fst,
+    bench_iter,
+    bench_top_k,
+    bench_filter
 );
 criterion_main!(benches);
@@ -0,0 +1,17 @@
+//! Checksums the embedded wordlist once at build time, so the embedded lookup index can be checked
+//! against it without hashing the whole wordlist on every startup
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let wordlist = Path::new("src").join("freq_200k.txt");
+    println!("cargo:rerun-if-changed={}", wordlist.display());
+
+    let bytes = fs::read(&wordlist)
+        .unwrap_or_else(|_| panic!("Unable to read wordlist '{}'", wordlist.display()));
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("wordlist_checksum.rs");
+    fs::write(&out, format!("{}", crc32fast::hash(&bytes)))
+        .unwrap_or_else(|_| panic!("Unable to write '{}'", out.display()));
+}
@@ -3,14 +3,70 @@ import("./pkg").then(module => {
     
     window.helper = new module.WordSearcherWrapper();
 
-    $("#given_letters, #filter").prop("disabled", false);
+    $("#given_letters, #filter, #dictionary, #alphabet, #dictionary_file").prop("disabled", false);
+
+    // lookups throw a descriptive message on bad input, show it in place of the results
+    const show = lookup => {
+        try {
+            $("#results").text(lookup());
+        } catch (e) {
+            $("#results").text(e);
+        }
+    };
 
     $("#given_letters").on("keyup", function() {
         $("#filter").val('');
-        $("#results").html(window.helper.lookup($(this).val()));
+        show(() => window.helper.lookup($(this).val()));
     });
 
     $("#filter").on("keyup", function() {
-        $("#results").html(window.helper.lookup_filter($("#given_letters").val(), $(this).val()));
+        show(() => window.helper.lookup_filter($("#given_letters").val(), $(this).val()));
+    });
+
+    const refreshDictionaries = () => {
+        $("#dictionary").empty();
+        for (const name of window.helper.dictionaries().split("\n")) {
+            $("#dictionary").append($("<option>").val(name).text(name));
+        }
+        $("#dictionary").val(window.helper.selected());
+    };
+    refreshDictionaries();
+
+    // serialized indices (like "dag.bin") end in ".bin", anything else is read as a wordlist in
+    // the selected alphabet
+    const loadDictionary = (name, source, buffer) => {
+        const bytes = new Uint8Array(buffer);
+        if (source.endsWith(".bin")) {
+            window.helper.load_dag(name, bytes);
+        } else {
+            window.helper.load_wordlist(name, bytes, $("#alphabet").val());
+        }
+        window.helper.select(name);
+        refreshDictionaries();
+    };
+
+    // e.g. `fetchDictionary("full", "dicts/full.txt")` from the console or other scripts
+    window.fetchDictionary = (name, url) => fetch(url)
+        .then(response => response.arrayBuffer())
+        .then(buffer => show(() => {
+            loadDictionary(name, url, buffer);
+            return window.helper.lookup_filter($("#given_letters").val(), $("#filter").val());
+        }));
+
+    $("#dictionary_file").on("change", function() {
+        const file = this.files[0];
+        if (file) {
+            file.arrayBuffer().then(buffer => show(() => {
+                loadDictionary(file.name, file.name, buffer);
+                return window.helper.lookup_filter($("#given_letters").val(), $("#filter").val());
+            }));
+        }
+    });
+
+    $("#dictionary").on("change", function() {
+        show(() => {
+            window.helper.select($(this).val());
+            return window.helper.lookup_filter($("#given_letters").val(), $("#filter").val());
+        });
     });
 });
@@ -0,0 +1,409 @@
+use crate::{AlphaMultiset, Error, Result, MAX_CHAR_REP, MAX_LETTERS};
+use serde::{Deserialize, Serialize};
+
+const ASCII_LETTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// The letters of a language, each of which gets its own count in an `AlphaMultiset`
+///
+/// Folding rules map characters which aren't letters of their own onto one, like `á` onto `a` in
+/// Spanish, where accents don't matter in word games. Characters are lowercased before folding.
+///
+/// The built-in alphabets start with the ASCII letters in order, so English sets keep their meaning
+/// in any of them.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Alphabet {
+    /// letters in the order of their counts in an `AlphaMultiset`
+    letters: Vec<char>,
+    /// `(from, to)` pairs, sorted by `from`
+    folding: Vec<(char, char)>,
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl Alphabet {
+    pub fn new(letters: &str) -> Self {
+        Self::try_new(letters).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but reports characters which can't be letters, or too many letters, instead of
+    /// panicking
+    pub fn try_new(letters: &str) -> Result<Self> {
+        let mut this = Self {
+            letters: Vec::new(),
+            folding: Vec::new(),
+        };
+
+        for c in letters
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(lowercase)
+        {
+            if !c.is_alphabetic() || this.letters.contains(&c) {
+                return Err(Error::InvalidLetter(c));
+            }
+            this.letters.push(c);
+        }
+
+        if this.letters.len() > MAX_LETTERS {
+            return Err(Error::TooManyLetters {
+                count: this.letters.len(),
+                max: MAX_LETTERS,
+            });
+        }
+
+        Ok(this)
+    }
+
+    /// Fold each `from` character onto the letter `to`, replacing any earlier rule for `from`
+    pub fn with_folding<I: IntoIterator<Item = (char, char)>>(mut self, rules: I) -> Self {
+        for (from, to) in rules {
+            let (from, to) = (lowercase(from), lowercase(to));
+            match self.folding.binary_search_by_key(&from, |&(f, _)| f) {
+                Ok(i) => self.folding[i].1 = to,
+                Err(i) => self.folding.insert(i, (from, to)),
+            }
+        }
+
+        self
+    }
+
+    pub fn english() -> Self {
+        Self::new(ASCII_LETTERS)
+    }
+
+    /// English letters and `ñ`, with accented vowels folded onto plain ones
+    pub fn spanish() -> Self {
+        Self::new(&format!("{}ñ", ASCII_LETTERS)).with_folding(vec![
+            ('á', 'a'),
+            ('é', 'e'),
+            ('í', 'i'),
+            ('ó', 'o'),
+            ('ú', 'u'),
+            ('ü', 'u'),
+        ])
+    }
+
+    /// English letters and `ä`, `ö`, `ü` and `ß`
+    pub fn german() -> Self {
+        Self::new(&format!("{}äöüß", ASCII_LETTERS))
+    }
+
+    /// English letters and the accented vowels, with the look-alikes `õ` and `û` (from encodings
+    /// without `ő` and `ű`) folded onto the real ones
+    pub fn hungarian() -> Self {
+        Self::new(&format!("{}áéíóöőúüű", ASCII_LETTERS)).with_folding(vec![
+            ('õ', 'ő'),
+            ('ô', 'ő'),
+            ('û', 'ű'),
+        ])
+    }
+
+    /// One of the built-in alphabets, by its (case insensitive) English name
+    pub fn from_name(name: &str) -> Result<Self> {
+        match &*name.trim().to_lowercase() {
+            "english" => Ok(Self::english()),
+            "spanish" => Ok(Self::spanish()),
+            "german" => Ok(Self::german()),
+            "hungarian" => Ok(Self::hungarian()),
+            _ => Err(Error::UnknownAlphabet(name.to_string())),
+        }
+    }
+
+    /// Number of letters
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    pub fn letters(&self) -> &[char] {
+        &self.letters
+    }
+
+    /// The folding rules as `(from, to)` pairs, sorted by `from`
+    pub fn folding(&self) -> &[(char, char)] {
+        &self.folding
+    }
+
+    /// Whether every letter is an ASCII letter
+    pub fn is_ascii(&self) -> bool {
+        self.letters.iter().all(char::is_ascii)
+    }
+
+    /// The letter with index `i`
+    pub fn letter(&self, i: usize) -> Option<char> {
+        self.letters.get(i).copied()
+    }
+
+    /// Lowercase `c` and apply the folding rules to it
+    pub fn fold(&self, c: char) -> char {
+        let c = lowercase(c);
+
+        match self.folding.binary_search_by_key(&c, |&(f, _)| f) {
+            Ok(i) => self.folding[i].1,
+            Err(_) => c,
+        }
+    }
+
+    /// The index of the letter `c` folds onto, if it folds onto one
+    pub fn index(&self, c: char) -> Option<usize> {
+        let c = self.fold(c);
+
+        // the built-in alphabets start with the ASCII letters
+        if c.is_ascii_lowercase() {
+            let i = c as usize - 'a' as usize;
+            if self.letters.get(i) == Some(&c) {
+                return Some(i);
+            }
+        }
+
+        self.letters.iter().position(|&l| l == c)
+    }
+
+    /// Whether `c` folds onto a letter
+    pub fn contains(&self, c: char) -> bool {
+        self.index(c).is_some()
+    }
+
+    pub fn to_set(&self, word: &str) -> AlphaMultiset {
+        self.try_to_set(word).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Count the letters of `word`, where `?` or `*` are blanks and whitespace is skipped
+    pub fn try_to_set(&self, word: &str) -> Result<AlphaMultiset> {
+        self.try_chars_to_set(word.chars().filter(|c| !c.is_whitespace()))
+    }
+
+    /// Count letters, where `?` or `*` are blanks, reporting letters which aren't part of this
+    /// alphabet and any other character
+    pub fn try_chars_to_set<I: IntoIterator<Item = char>>(
+        &self,
+        chars: I,
+    ) -> Result<AlphaMultiset> {
+        let mut counts = [0usize; MAX_LETTERS];
+        let mut blanks = 0;
+
+        for c in chars {
+            if is_blank(c) {
+                blanks += 1;
+            } else if let Some(i) = self.index(c) {
+                counts[i] += 1;
+            } else if c.is_alphabetic() {
+                return Err(Error::NotInAlphabet(c));
+            } else {
+                return Err(Error::InvalidChar(c));
+            }
+        }
+
+        let too_many = |ch, count| Error::TooManyRepetitions {
+            ch,
+            count,
+            max: MAX_CHAR_REP,
+        };
+
+        if blanks > MAX_CHAR_REP {
+            return Err(too_many('?', blanks));
+        }
+        let mut small_counts = [0; MAX_LETTERS];
+        for (i, &count) in counts.iter().enumerate() {
+            if count > MAX_CHAR_REP {
+                return Err(too_many(self.letters[i], count));
+            }
+            small_counts[i] = count as u8;
+        }
+
+        Ok(AlphaMultiset::try_from_counts(&small_counts, self)?.with_blanks(blanks as u8))
+    }
+
+    /// Show the letters of `set` in the order of this alphabet, followed by a `?` for each blank
+    pub fn set_to_string(&self, set: &AlphaMultiset) -> String {
+        let mut s = String::with_capacity(set.len());
+
+        for (&letter, &count) in self.letters.iter().zip(set.char_counts().iter()) {
+            s.extend(std::iter::repeat(letter).take(count as usize));
+        }
+        s.extend(std::iter::repeat('?').take(set.blanks()));
+
+        return s;
+    }
+}
+
+/// Whether `c` is a blank tile in looked up letters
+pub(crate) fn is_blank(c: char) -> bool {
+    c == '?' || c == '*'
+}
+
+/// The lowercase version of `c`, if it's a single character
+pub(crate) fn lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_lanes() {
+        let english = Alphabet::english();
+        assert_eq!(english.to_set("Bcbc az"), AlphaMultiset::from("bcbcaz"));
+        assert!(matches!(
+            english.try_to_set("ab1c"),
+            Err(Error::InvalidChar('1'))
+        ));
+        assert!(matches!(
+            english.try_to_set("bcbc-az"),
+            Err(Error::InvalidChar('-'))
+        ));
+        assert_eq!(
+            Alphabet::german().to_set("zebra"),
+            AlphaMultiset::from("zebra")
+        );
+        assert!(matches!(
+            english.try_to_set("año"),
+            Err(Error::NotInAlphabet('ñ'))
+        ));
+    }
+
+    #[test]
+    fn test_folding() {
+        let spanish = Alphabet::spanish();
+        assert_eq!(spanish.to_set("Canción"), spanish.to_set("cancion"));
+        assert_ne!(spanish.to_set("año"), spanish.to_set("ano"));
+        assert_eq!(spanish.set_to_string(&spanish.to_set("ñandú?")), "adnuñ?");
+
+        let hungarian = Alphabet::hungarian();
+        assert_eq!(hungarian.to_set("tõ"), hungarian.to_set("tő"));
+        assert_ne!(hungarian.to_set("tö"), hungarian.to_set("tő"));
+        assert!(!hungarian
+            .to_set("őrült")
+            .has_subset(&hungarian.to_set("rű")));
+    }
+
+    #[test]
+    fn test_extra_letters_subset() {
+        let hungarian = Alphabet::hungarian();
+        let rack = hungarian.to_set("űűőéb?");
+
+        assert!(rack.has_subset(&hungarian.to_set("űőb")));
+        assert!(rack.has_subset(&hungarian.to_set("űűűb")));
+        assert!(!rack.has_subset(&hungarian.to_set("űűűűb")));
+        assert_eq!(rack.difference(&hungarian.to_set("ű")).len(), 5);
+    }
+
+    #[test]
+    fn test_invalid_alphabets() {
+        assert!(matches!(
+            Alphabet::try_new("abca"),
+            Err(Error::InvalidLetter('a'))
+        ));
+        assert!(matches!(
+            Alphabet::try_new("ab?"),
+            Err(Error::InvalidLetter('?'))
+        ));
+        assert!(matches!(
+            Alphabet::try_new(&format!("{}αβγδεζηθικλμνξ", ASCII_LETTERS)),
+            Err(Error::TooManyLetters { count: 40, .. })
+        ));
+        assert!(Alphabet::from_name("Klingon").is_err());
+    }
+
+    #[test]
+    fn test_searchers_in_alphabet() {
+        use crate::{
+            AutomatonSearcher, DAGSearcher, ExpSearcher, SimpleSearcher, TrieSearcher,
+            WordSearcher, Wordlist,
+        };
+
+        let wordlist = || {
+            Wordlist::from_bytes("año 5\nano 4\nniño 3\nCanción 2\n".as_bytes())
+                .with_alphabet(Alphabet::spanish())
+        };
+        let searchers: Vec<Box<dyn WordSearcher>> = vec![
+            Box::new(AutomatonSearcher::from_wordlist(wordlist())),
+            Box::new(DAGSearcher::from_wordlist(wordlist())),
+            Box::new(ExpSearcher::from_wordlist(wordlist())),
+            Box::new(SimpleSearcher::from_wordlist(wordlist())),
+            Box::new(TrieSearcher::from_wordlist(wordlist())),
+        ];
+
+        for searcher in searchers {
+            let mut words: Vec<_> = searcher
+                .lookup("oñA")
+                .iter()
+                .map(|w| w.to_string())
+                .collect();
+            words.sort();
+            assert_eq!(words, vec!["año"]);
+
+            let mut words: Vec<_> = searcher
+                .lookup("o?a")
+                .iter()
+                .map(|w| w.to_string())
+                .collect();
+            words.sort();
+            assert_eq!(words, vec!["ano", "año"]);
+
+            let words = searcher.lookup_filter("ncioacn", "can___n");
+            assert_eq!(words.len(), 1);
+            assert_eq!(&*words[0], "cancion");
+        }
+    }
+
+    #[test]
+    fn test_accented_filters() {
+        use crate::{
+            AutomatonSearcher, ColumnarSearcher, DAGIndex, DAGSearcher, ExpSearcher, FstSearcher,
+            SimpleSearcher, TrieSearcher, WordSearcher, Wordlist,
+        };
+
+        let wordlist = || {
+            Wordlist::from_bytes("año 5\nano 4\nniño 3\nCanción 2\n".as_bytes())
+                .with_alphabet(Alphabet::spanish())
+        };
+        let dag = DAGSearcher::from_wordlist(wordlist());
+        let searchers: Vec<Box<dyn WordSearcher>> = vec![
+            Box::new(AutomatonSearcher::from_wordlist(wordlist())),
+            Box::new(ColumnarSearcher::from_wordlist(wordlist())),
+            Box::new(DAGIndex::try_from_bytes(dag.to_index_bytes()).unwrap()),
+            Box::new(ExpSearcher::from_wordlist(wordlist())),
+            Box::new(FstSearcher::from_wordlist(wordlist())),
+            Box::new(SimpleSearcher::from_wordlist(wordlist())),
+            Box::new(TrieSearcher::from_wordlist(wordlist())),
+            Box::new(dag),
+        ];
+
+        // filter letters are folded like the words are, whether or not they are accented
+        let filters = [
+            "canción",
+            "c_nción",
+            "CANCIÓN",
+            "/ción$",
+            "/c[áa]n.*",
+            "cancion",
+        ];
+
+        for searcher in searchers {
+            for filter in filters.iter() {
+                let words = searcher.lookup_filter("ncioacn", filter);
+                assert_eq!(words.len(), 1, "filter {}", filter);
+                assert_eq!(&*words[0], "cancion");
+            }
+
+            let words = searcher.lookup_filter("a?o", "_ñ_");
+            assert_eq!(words.len(), 1);
+            assert_eq!(&*words[0], "año");
+            assert_eq!(searcher.lookup_filter("a?o", "/[^ñ]ñ.").len(), 1);
+        }
+    }
+}
@@ -7,12 +7,20 @@ use wordscapes_helper::*;
 
 const DAG_FILENAME: &str = "dag.bin";
 
+const USAGE: &str = "Usage: gen_files [--scrabble] [--min-len <n>] [--max-len <n>] \
+[--punctuation <strip|drop|reject>] [--other-chars <strip|drop|reject>] [--no-case-folding]";
+
 fn main() {
-    let helper = DAGSearcher::from_embedded_wordlist();
+    let policy = parse_policy(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(1)
+    });
+
+    let helper = DAGSearcher::from_wordlist(Wordlist::embedded().with_normalization(policy));
     println!("Constructed wordlist DAG");
 
-    let binarr = bincode::serialize(&helper).expect("Unable to serialize DAG");
-    println!("Serialized DAG to Vec<u8>");
+    let binarr = helper.to_index_bytes();
+    println!("Laid out DAG as Vec<u8>");
 
     let path = &Path::new("src").join("word_searcher").join(DAG_FILENAME);
     let file = File::create(path)
@@ -24,3 +32,42 @@ fn main() {
         .unwrap_or_else(|_| panic!("Couldn't write bytes to '{}'", path.display()));
     println!("Serialized Vec<u8> to '{}'", path.display());
 }
+
+/// Build the normalization policy of the embedded wordlist from command line flags
+fn parse_policy<I: Iterator<Item = String>>(mut args: I) -> Result<NormalizationPolicy, String> {
+    let mut policy = NormalizationPolicy::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for '{}'", arg));
+
+        match &*arg {
+            "--scrabble" => policy = NormalizationPolicy::scrabble(),
+            "--min-len" => policy.min_len = parse_len(&value()?)?,
+            "--max-len" => policy.max_len = parse_len(&value()?)?,
+            "--punctuation" => policy.punctuation = parse_handling(&value()?)?,
+            "--other-chars" => policy.other_chars = parse_handling(&value()?)?,
+            "--no-case-folding" => policy.fold_case = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0)
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    return Ok(policy);
+}
+
+fn parse_len(s: &str) -> Result<usize, String> {
+    s.parse()
+        .map_err(|_| format!("Expected a word length, but got '{}'", s))
+}
+
+fn parse_handling(s: &str) -> Result<CharHandling, String> {
+    match s {
+        "strip" => Ok(CharHandling::Strip),
+        "drop" => Ok(CharHandling::Drop),
+        "reject" => Ok(CharHandling::Reject),
+        _ => Err(format!("Expected strip, drop or reject, but got '{}'", s)),
+    }
+}
@@ -0,0 +1,25 @@
+use wordscapes_helper::*;
+
+/// Letters of the lookups benchmarked in "benches/bench_all.rs"
+const LETTERS: [&str; 4] = ["abc", "abcdef", "abcdefghijkl", "abcdefghijklmnopqrstuvwx"];
+
+/// Print the work each searcher does for the benchmarked lookups, to tell which of them regressed
+/// when a benchmark slows down
+fn main() {
+    let searchers: Vec<(&str, Box<dyn WordSearcher>)> = vec![
+        ("automaton", Box::new(AutomatonSearcher::default())),
+        ("columnar", Box::new(ColumnarSearcher::default())),
+        ("dag", Box::new(DAGSearcher::default())),
+        ("exp", Box::new(ExpSearcher::default())),
+        ("fst", Box::new(FstSearcher::default())),
+        ("simple", Box::new(SimpleSearcher::default())),
+        ("trie", Box::new(TrieSearcher::default())),
+    ];
+
+    for letters in LETTERS.iter() {
+        for (name, searcher) in searchers.iter() {
+            let (_, stats) = searcher.lookup_stats(letters);
+            println!("{} `{}`: {}", name, letters, stats);
+        }
+    }
+}
@@ -12,11 +12,13 @@ fn main() {
     };
 
     let start = std::time::Instant::now();
+    let helper = DAGIndex::embedded();
     // let helper = DAGSearcher::default();
     // let helper = AutomatonSearcher::default();
     // let helper = TrieSearcher::default();
-    let helper = SimpleSearcher::default();
+    // let helper = SimpleSearcher::default();
     // let helper = ExpSearcher::default();
+    // let helper = HybridSearcher::default();
     let elapsed = start.elapsed();
     println!("Setup took {:?}", elapsed);
 
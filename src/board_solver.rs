@@ -3,7 +3,7 @@ use petgraph::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::{DAGSearcher, Filter, Word, WordSearcher};
+use crate::{Alphabet, DAGIndex, Error, Filter, Result, Word, WordSearcher};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BoardSolver {
@@ -19,9 +19,33 @@ impl BoardSolver {
         }
     }
 
+    /// Like `from_board`, but reports bad letters or a malformed board instead of panicking
+    pub fn try_from_board(letters: &str, board: &str) -> Result<Self> {
+        Self::try_from_board_in(&Alphabet::english(), letters, board)
+    }
+
+    /// Like `try_from_board`, for letters and a board in the language of `alphabet`
+    pub fn try_from_board_in(alphabet: &Alphabet, letters: &str, board: &str) -> Result<Self> {
+        alphabet.try_to_set(letters)?;
+
+        Ok(Self {
+            letters: letters.to_string(),
+            relations: Relations::from(&RawBoard::parse(board)?),
+        })
+    }
+
     pub fn first_n_solutions(&self, n: usize) -> Vec<Relations> {
+        self.first_n_solutions_with(&DAGIndex::embedded(), n)
+    }
+
+    /// Like `first_n_solutions`, taking words from `searcher`, e.g. one of a non-English wordlist
+    pub fn first_n_solutions_with<S: WordSearcher + ?Sized>(
+        &self,
+        searcher: &S,
+        n: usize,
+    ) -> Vec<Relations> {
         // let start = std::time::Instant::now();
-        let words = DAGSearcher::default().lookup(&*self.letters);
+        let words = searcher.lookup(&*self.letters);
         // println!(
         //     "Took {:?} to load searcher and lookup letters: {:?}",
         //     start.elapsed(),
@@ -82,7 +106,7 @@ impl ConstraintSet {
         let mut wordmap = HashMap::new();
         for word in words {
             wordmap
-                .entry(word.len())
+                .entry(word.chars().count())
                 .or_insert_with(Vec::new)
                 .push(word);
         }
@@ -169,7 +193,7 @@ impl ConstraintSet {
 
                     // println!("{}, {} | {}, {}", a.len(), cc.0, b.len(), cc.1);7
 
-                    if a.as_bytes()[cc.0] != b.as_bytes()[cc.1] {
+                    if a.chars().nth(cc.0) != b.chars().nth(cc.1) {
                         return false;
                     }
                 }
@@ -203,7 +227,7 @@ pub struct CharacterConstraint(pub usize, pub usize);
 pub enum BoardTile {
     Empty,
     BlankChar,
-    Char(u8),
+    Char(char),
 }
 
 impl BoardTile {
@@ -226,11 +250,11 @@ impl Default for BoardTile {
     }
 }
 
-impl std::convert::From<u8> for BoardTile {
-    fn from(c: u8) -> Self {
-        if (c as char).is_alphabetic() {
+impl std::convert::From<char> for BoardTile {
+    fn from(c: char) -> Self {
+        if c.is_alphabetic() {
             BoardTile::Char(c)
-        } else if c == b'#' {
+        } else if c == '#' {
             BoardTile::BlankChar
         } else {
             BoardTile::Empty
@@ -238,6 +262,12 @@ impl std::convert::From<u8> for BoardTile {
     }
 }
 
+impl std::convert::From<u8> for BoardTile {
+    fn from(c: u8) -> Self {
+        Self::from(c as char)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct RawBoard(ndarray::Array2<BoardTile>);
 
@@ -246,6 +276,43 @@ impl RawBoard {
         Self(ndarray::Array2::default((height, width)))
     }
 
+    /// Parse a board with one line per row, where letters are known tiles,
+    /// `#` are unknown tiles and anything else is empty space
+    pub fn parse(text: &str) -> Result<Self> {
+        let lines: Vec<_> = text
+            .trim_matches(|c| c == '\n' || c == '\r')
+            .lines()
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect();
+
+        if lines.is_empty() || lines[0].is_empty() {
+            return Err(Error::EmptyBoard);
+        }
+
+        for i in 1..lines.len() {
+            if lines[i].len() != lines[0].len() {
+                return Err(Error::RaggedBoard {
+                    row: i,
+                    expected: lines[0].len(),
+                    found: lines[i].len(),
+                });
+            }
+        }
+
+        let width = lines[0].len();
+        let height = lines.len();
+
+        let mut arr = ndarray::Array2::default((height, width));
+
+        for r in 0..height {
+            for c in 0..width {
+                arr[[r, c]] = BoardTile::from(lines[r][c]);
+            }
+        }
+
+        Ok(Self(arr))
+    }
+
     pub fn height(&self) -> usize {
         self.0.raw_dim()[0]
     }
@@ -271,7 +338,7 @@ impl std::fmt::Display for RawBoard {
                     match self[[r, c]] {
                         BoardTile::Empty => ' ',
                         BoardTile::BlankChar => '#',
-                        BoardTile::Char(c) => c as char,
+                        BoardTile::Char(c) => c,
                     }
                 )?;
             }
@@ -286,7 +353,7 @@ impl std::fmt::Display for RawBoard {
                 match self[[self.height() - 1, c]] {
                     BoardTile::Empty => '_',
                     BoardTile::BlankChar => '#',
-                    BoardTile::Char(c) => c as char,
+                    BoardTile::Char(c) => c,
                 }
             )?;
         }
@@ -298,38 +365,7 @@ impl std::fmt::Display for RawBoard {
 
 impl<'a> std::convert::From<&'a str> for RawBoard {
     fn from(text: &'a str) -> Self {
-        let lines: Vec<_> = text
-            .trim_matches(|c| c == '\n' || c == '\r')
-            .lines()
-            .map(|l| l.as_bytes())
-            .collect();
-
-        debug_assert!(lines.len() > 0);
-
-        for i in 0..lines.len() - 1 {
-            debug_assert_eq!(
-                lines[i].len(),
-                lines[i + 1].len(),
-                "Lines {} and {} have different lengths ({} vs {})",
-                i,
-                i + 1,
-                lines[i].len(),
-                lines[i + 1].len()
-            );
-        }
-
-        let width = lines[0].len();
-        let height = lines.len();
-
-        let mut arr = ndarray::Array2::default((height, width));
-
-        for r in 0..height {
-            for c in 0..width {
-                arr[[r, c]] = BoardTile::from(lines[r][c]);
-            }
-        }
-
-        Self(arr)
+        Self::parse(text).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -417,7 +453,8 @@ impl std::convert::Into<RawBoard> for Relations {
                     node.start_pos[1] + i * node.dir_vector[1],
                 ];
 
-                board[[r, c]] = BoardTile::Char(node.candidate.as_ref().unwrap().as_bytes()[i]);
+                let candidate = node.candidate.as_ref().unwrap();
+                board[[r, c]] = BoardTile::Char(candidate.chars().nth(i).unwrap());
             }
         }
 
@@ -485,7 +522,7 @@ impl std::convert::From<&RawBoard> for Relations {
                             })
                             .map(|[r, c]| {
                                 if let BoardTile::Char(c) = board[[r, c]] {
-                                    c as char
+                                    c
                                 } else {
                                     '-'
                                 }
@@ -559,6 +596,24 @@ __#__
         );
     }
 
+    #[test]
+    fn test_ragged_board_parse() {
+        assert!(matches!(
+            RawBoard::parse("###\n##\n###"),
+            Err(Error::RaggedBoard {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert!(matches!(RawBoard::parse("\n\n"), Err(Error::EmptyBoard)));
+
+        let board = RawBoard::parse("ñ#\n_ü").unwrap();
+        assert_eq!(board.width(), 2);
+        assert_eq!(board[[0, 0]], BoardTile::Char('ñ'));
+        assert_eq!(board[[1, 1]], BoardTile::Char('ü'));
+    }
+
     #[test]
     fn test_easy_solve() {
         let start = std::time::Instant::now();
@@ -0,0 +1,230 @@
+/// Everything that can go wrong when building an index or parsing user input
+#[derive(Debug)]
+pub enum Error {
+    /// A character which isn't a letter of the alphabet in use, nor a blank where those are allowed
+    InvalidChar(char),
+
+    /// A letter which occurs more often than an `AlphaMultiset` can count
+    TooManyRepetitions {
+        ch: char,
+        count: usize,
+        max: usize,
+    },
+
+    /// A letter which isn't part of the alphabet of a lookup index
+    NotInAlphabet(char),
+
+    /// A character which can't be added to an alphabet, as it isn't a letter or already is one
+    InvalidLetter(char),
+
+    /// More letters than an `AlphaMultiset` has room for
+    TooManyLetters {
+        count: usize,
+        max: usize,
+    },
+
+    /// An alphabet name which isn't one of the built-in alphabets
+    UnknownAlphabet(String),
+
+    /// A wordlist line which isn't of the form `word<whitespace>frequency`
+    MalformedLine(String),
+
+    /// Any error which occurred while processing line `line` (1-based) of a wordlist
+    Wordlist {
+        line: usize,
+        source: Box<Error>,
+    },
+
+    /// A filter character which is neither a letter, a blank nor a digit, at char index `position`
+    InvalidFilter {
+        ch: char,
+        position: usize,
+    },
+
+    /// A filter run length (like the `12` in `a12`) which is too long to be a word
+    InvalidRunLength {
+        run: String,
+        position: usize,
+    },
+
+    /// A filter group or class, opened at char index `position`, which is never closed
+    UnclosedFilter {
+        open: char,
+        position: usize,
+    },
+
+    /// A board with no rows
+    EmptyBoard,
+
+    /// A board row (0-based) whose length differs from the first row's
+    RaggedBoard {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+
+    /// A wordlist or index file which couldn't be opened
+    Open {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    /// A dictionary name which isn't loaded
+    UnknownDictionary(String),
+
+    /// A searcher named `name` whose alphabet differs from the one of the searchers it's combined
+    /// with
+    AlphabetMismatch(String),
+
+    /// A serialized index which couldn't be decoded
+    Deserialize(bincode::Error),
+
+    /// A flat lookup index (like "dag.bin") whose layout doesn't check out, for the given reason
+    CorruptIndex(&'static str),
+
+    /// A lookup index with another layout version, or another `what` of its layout, than this
+    /// build reads
+    IndexLayout {
+        what: &'static str,
+        found: u32,
+        expected: u32,
+    },
+
+    /// A lookup index whose contents don't add up to its checksum, e.g. as it was modified
+    IndexChecksum {
+        found: u32,
+        expected: u32,
+    },
+
+    /// A lookup index built from another wordlist than the one it's used with, by wordlist checksum
+    StaleIndex {
+        found: u32,
+        expected: u32,
+    },
+
+    Io(std::io::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Attach the (1-based) line number of a wordlist to this error
+    pub(crate) fn at_line(self, line: usize) -> Self {
+        Error::Wordlist {
+            line,
+            source: Box::new(self),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::InvalidChar(c) => write!(
+                f,
+                "Unexpected character '{}', which is not a letter of the alphabet in use",
+                c
+            ),
+            Error::TooManyRepetitions { ch, count, max } => write!(
+                f,
+                "Can only handle {} repetitions of chars, but '{}' occurs {} times",
+                max, ch, count
+            ),
+            Error::NotInAlphabet(c) => write!(f, "'{}' is not a letter of the alphabet in use", c),
+            Error::InvalidLetter(c) => write!(
+                f,
+                "'{}' can't be added to an alphabet, as it isn't a letter or already is one",
+                c
+            ),
+            Error::TooManyLetters { count, max } => write!(
+                f,
+                "Can only handle alphabets of up to {} letters, but got {}",
+                max, count
+            ),
+            Error::UnknownAlphabet(name) => write!(f, "No alphabet named '{}'", name),
+            Error::MalformedLine(line) => {
+                write!(f, "Expected `word frequency`, but got '{}'", line)
+            }
+            Error::Wordlist { line, source } => write!(f, "Wordlist line {}: {}", line, source),
+            Error::InvalidFilter { ch, position } => write!(
+                f,
+                "Unexpected character '{}' at position {} of filter",
+                ch, position
+            ),
+            Error::InvalidRunLength { run, position } => write!(
+                f,
+                "Run length '{}' at position {} of filter is too long",
+                run, position
+            ),
+            Error::UnclosedFilter { open, position } => {
+                write!(f, "Unclosed '{}' at position {} of filter", open, position)
+            }
+            Error::EmptyBoard => write!(f, "Board has no rows"),
+            Error::RaggedBoard {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Board row {} has length {}, but previous rows have length {}",
+                row, found, expected
+            ),
+            Error::Open { path, source } => {
+                write!(f, "Unable to open '{}': {}", path.display(), source)
+            }
+            Error::UnknownDictionary(name) => write!(f, "No dictionary named '{}' is loaded", name),
+            Error::AlphabetMismatch(name) => write!(
+                f,
+                "Searcher '{}' has another alphabet than the searchers it's combined with",
+                name
+            ),
+            Error::Deserialize(e) => write!(f, "Unable to deserialize index: {}", e),
+            Error::CorruptIndex(reason) => write!(f, "Unable to use lookup index: {}", reason),
+            Error::IndexLayout {
+                what,
+                found,
+                expected,
+            } => write!(
+                f,
+                "Lookup index has {} {}, but this build reads {}; regenerate it with gen_files",
+                what, found, expected
+            ),
+            Error::IndexChecksum { found, expected } => write!(
+                f,
+                "Lookup index is corrupt, its checksum is {:08x} instead of {:08x}",
+                found, expected
+            ),
+            Error::StaleIndex { found, expected } => write!(
+                f,
+                "Lookup index was built from wordlist {:08x} instead of {:08x}; regenerate it with \
+                 gen_files",
+                found, expected
+            ),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Wordlist { source, .. } => Some(&**source),
+            Error::Open { source, .. } => Some(source),
+            Error::Deserialize(e) => Some(&**e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::convert::From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl std::convert::From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::Deserialize(e)
+    }
+}
@@ -1,18 +1,42 @@
+use crate::{alphabet::lowercase, Alphabet, Error, Result};
 use serde::{Deserialize, Serialize};
 
+mod pattern;
+
+pub use pattern::Pattern;
+
+/// Letters of filters are kept as they are given, and folded like the words of an index when a
+/// searcher matches them with `matches_in`, so `canción` matches "cancion" in Spanish
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub enum Filter {
     Permissive,
     Restrictive,
-    ExactString(Vec<u8>),
+    ExactString(Vec<char>),
     LengthRestricted(usize),
-    Mixed(Vec<u8>),
+    /// letters, with `_` for any letter
+    Mixed(Vec<char>),
+    Pattern(Pattern),
 }
 
 impl Filter {
+    /// Parse a filter, ignoring any characters which have no meaning in a filter
+    ///
+    /// Filters starting with `/` are parsed as a `Pattern`, any others as shorthand filters.
     pub fn new<S: AsRef<str>>(filter: S) -> Self {
-        let filter_lowercase = filter.as_ref().to_lowercase();
-        let filter = filter_lowercase.as_bytes();
+        Self::parse_impl(filter.as_ref(), false).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parse a filter, reporting any characters which have no meaning in a filter
+    pub fn parse<S: AsRef<str>>(filter: S) -> Result<Self> {
+        Self::parse_impl(filter.as_ref(), true)
+    }
+
+    fn parse_impl(filter: &str, strict: bool) -> Result<Self> {
+        if filter.trim_start().starts_with(pattern::PATTERN_MARKER) {
+            return Pattern::parse(filter, strict).map(Filter::Pattern);
+        }
+
+        let filter: Vec<char> = filter.to_lowercase().chars().collect();
         let mut processed_filter = Vec::new();
         let mut only_alpha = true;
         let mut only_free = true;
@@ -20,67 +44,122 @@ impl Filter {
         let mut i = 0;
         while i < filter.len() {
             let mut buf = String::new();
-            while i < filter.len() && (filter[i] as char).is_numeric() {
-                buf.push(filter[i] as char);
+            while i < filter.len() && filter[i].is_ascii_digit() {
+                buf.push(filter[i]);
                 i += 1;
             }
 
             if !buf.is_empty() {
-                let num: u8 = buf.parse().unwrap();
-                processed_filter.extend((0..num).map(|_| b'_'));
+                let num: u8 = buf.parse().map_err(|_| Error::InvalidRunLength {
+                    position: i - buf.len(),
+                    run: buf,
+                })?;
+                processed_filter.extend((0..num).map(|_| '_'));
                 only_alpha = false;
 
                 i -= 1;
-            } else if filter[i] == b'-' || filter[i] == b'_' || filter[i] == b'#' {
-                processed_filter.push(b'_');
+            } else if filter[i] == '-' || filter[i] == '_' || filter[i] == '#' {
+                processed_filter.push('_');
                 only_alpha = false;
-            } else if (filter[i] as char).is_ascii_alphabetic() {
+            } else if filter[i].is_alphabetic() {
                 processed_filter.push(filter[i]);
                 only_free = false;
+            } else if strict && !filter[i].is_whitespace() {
+                return Err(Error::InvalidFilter {
+                    ch: filter[i],
+                    position: i,
+                });
             }
 
             i += 1;
         }
 
-        if only_alpha {
+        Ok(if only_alpha {
             Filter::ExactString(processed_filter)
         } else if only_free {
             Filter::LengthRestricted(processed_filter.len())
         } else {
             Filter::Mixed(processed_filter)
-        }
+        })
     }
 
     pub fn matches(&self, word: &str) -> bool {
+        self.matches_folded(word, &lowercase)
+    }
+
+    /// Like `matches`, but with the letters of the filter and of `word` folded onto the letters of
+    /// `alphabet`, as the words of a lookup index are
+    pub fn matches_in(&self, word: &str, alphabet: &Alphabet) -> bool {
+        self.matches_folded(word, &|c| alphabet.fold(c))
+    }
+
+    fn matches_folded(&self, word: &str, fold: &dyn Fn(char) -> char) -> bool {
         match self {
             Filter::Permissive => true,
             Filter::Restrictive => false,
-            Filter::ExactString(v) => word.as_bytes() == &v[..],
-            Filter::LengthRestricted(len) => word.len() == *len,
+            Filter::ExactString(v) => word.chars().map(fold).eq(v.iter().map(|&c| fold(c))),
+            Filter::LengthRestricted(len) => word.chars().count() == *len,
             Filter::Mixed(v) => {
-                if v.len() != word.len() {
+                if v.len() != word.chars().count() {
                     return false;
                 }
 
-                for (&fc, &wc) in v.iter().zip(word.as_bytes()) {
-                    if fc == b'_' {
+                for (&fc, wc) in v.iter().zip(word.chars()) {
+                    if fc == '_' {
                         continue;
                     }
-                    if !fc.eq_ignore_ascii_case(&wc) {
+                    if fold(fc) != fold(wc) {
                         return false;
                     }
                 }
 
                 return true;
             }
+            Filter::Pattern(p) => p.matches_folded(word, fold),
         }
     }
 
+    /// The length of words this filter matches, or of the shortest ones for patterns
     pub fn len(&self) -> usize {
         match self {
             Filter::Permissive | Filter::Restrictive => 0,
             Filter::LengthRestricted(len) => *len,
             Filter::ExactString(v) | Filter::Mixed(v) => v.len(),
+            Filter::Pattern(p) => p.length_range().0,
+        }
+    }
+
+    /// The length of every word this filter matches, if it restricts the length to a single one
+    ///
+    /// `Filter::Restrictive` matches no words, which is the same as only matching empty ones
+    pub fn length(&self) -> Option<usize> {
+        match self.length_range() {
+            (min, Some(max)) if min == max => Some(min),
+            _ => None,
+        }
+    }
+
+    /// The shortest and, if there is one, the longest length of words this filter matches
+    pub fn length_range(&self) -> (usize, Option<usize>) {
+        match self {
+            Filter::Permissive => (0, None),
+            Filter::Restrictive => (0, Some(0)),
+            Filter::LengthRestricted(len) => (*len, Some(*len)),
+            Filter::ExactString(v) | Filter::Mixed(v) => (v.len(), Some(v.len())),
+            Filter::Pattern(p) => p.length_range(),
+        }
+    }
+
+    /// The letters fixed by this filter, which every word it matches contains, in sorted order
+    pub fn fixed_letters(&self) -> Vec<char> {
+        match self {
+            Filter::ExactString(v) | Filter::Mixed(v) => {
+                let mut letters: Vec<_> = v.iter().copied().filter(|&c| c != '_').collect();
+                letters.sort_unstable();
+                letters
+            }
+            Filter::Pattern(p) => p.fixed_letters(),
+            _ => Vec::new(),
         }
     }
 }
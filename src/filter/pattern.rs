@@ -0,0 +1,575 @@
+use crate::{alphabet::lowercase, Alphabet, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Leading character which marks a filter as a pattern, so `?`, `*` and `.` keep being ignored in
+/// shorthand filters as they always were
+pub(crate) const PATTERN_MARKER: char = '/';
+
+/// A regex-like filter over lowercase letters, which filters starting with `/` are parsed as
+///
+/// Besides the shorthand filter syntax (letters, `-`/`_`/`#` for any letter and digit run lengths)
+/// patterns support `.` for any letter, classes like `[aeiou]` and `[^s]`, groups of alternatives
+/// like `(ing|ed)`, and `*`, `+` and `?` to repeat the previous letter, class or group.
+///
+/// Without `^` or `$` a pattern has to match the whole word. With either of them only that end of
+/// the word is pinned, so `(ing|ed)$` matches any word ending in "ing" or "ed".
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+pub struct Pattern {
+    alternatives: Vec<Vec<Node>>,
+    anchored_start: bool,
+    anchored_end: bool,
+}
+
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+enum Node {
+    /// One letter out of a class
+    Class(Class),
+    /// Any one of several sequences
+    Group(Vec<Vec<Node>>),
+    /// A node repeated at least `min` and at most `max` times
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// A set of letters, or with `negated` every letter but those
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+struct Class {
+    /// sorted and without duplicates
+    letters: Vec<char>,
+    negated: bool,
+}
+
+impl Class {
+    fn any_letter() -> Self {
+        Self {
+            letters: Vec::new(),
+            negated: true,
+        }
+    }
+
+    fn letter(c: char) -> Self {
+        Self {
+            letters: vec![c],
+            negated: false,
+        }
+    }
+
+    /// Whether `c` is one of the letters of this class, or isn't for a negated class, once both are
+    /// folded with `fold`
+    fn matches(&self, c: char, fold: &dyn Fn(char) -> char) -> bool {
+        let c = fold(c);
+        c.is_alphabetic() && self.letters.iter().any(|&l| fold(l) == c) != self.negated
+    }
+
+    /// The only letter this class matches, if there is just one
+    fn single_letter(&self) -> Option<char> {
+        match &self.letters[..] {
+            &[c] if !self.negated => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl Pattern {
+    /// Parse a pattern, with or without the leading `/` of a filter, where `strict` reports any
+    /// characters which have no meaning in a pattern instead of ignoring them
+    pub fn parse(pattern: &str, strict: bool) -> Result<Self> {
+        let chars: Vec<char> = pattern.to_lowercase().chars().collect();
+        let mut parser = Parser {
+            chars: &chars,
+            pos: 0,
+            strict,
+        };
+
+        parser.skip_whitespace();
+        if parser.peek() == Some(PATTERN_MARKER) {
+            parser.pos += 1;
+        }
+        let anchored_start = parser.peek() == Some('^');
+        if anchored_start {
+            parser.pos += 1;
+        }
+
+        let alternatives = parser.parse_alternatives()?;
+
+        let anchored_end = parser.peek() == Some('$');
+        if anchored_end {
+            parser.pos += 1;
+            parser.skip_whitespace();
+        }
+
+        // only a closing parenthesis without an opening one can end the pattern early
+        if let Some(ch) = parser.peek() {
+            return Err(Error::InvalidFilter {
+                ch,
+                position: parser.pos,
+            });
+        }
+
+        let unanchored = !anchored_start && !anchored_end;
+
+        Ok(Self {
+            alternatives,
+            anchored_start: anchored_start || unanchored,
+            anchored_end: anchored_end || unanchored,
+        })
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        self.matches_folded(word, &lowercase)
+    }
+
+    /// Like `matches`, but with the letters of the pattern and of `word` folded onto the letters of
+    /// `alphabet`, as the words of a lookup index are
+    pub fn matches_in(&self, word: &str, alphabet: &Alphabet) -> bool {
+        self.matches_folded(word, &|c| alphabet.fold(c))
+    }
+
+    pub(super) fn matches_folded(&self, word: &str, fold: &dyn Fn(char) -> char) -> bool {
+        let word: Vec<char> = word.chars().collect();
+        let word = &word[..];
+
+        let starts = if self.anchored_start {
+            let mut starts = vec![false; word.len() + 1];
+            starts[0] = true;
+            starts
+        } else {
+            vec![true; word.len() + 1]
+        };
+
+        let ends = match_alternatives(&self.alternatives, word, &starts, fold);
+
+        if self.anchored_end {
+            ends[word.len()]
+        } else {
+            ends.contains(&true)
+        }
+    }
+
+    /// The shortest and, if there is one, the longest length of words this pattern matches
+    pub fn length_range(&self) -> (usize, Option<usize>) {
+        let (min, max) = alternatives_length_range(&self.alternatives);
+
+        if self.anchored_start && self.anchored_end {
+            (min, max)
+        } else {
+            (min, None)
+        }
+    }
+
+    /// The letters which every word this pattern matches contains, in sorted order
+    pub fn fixed_letters(&self) -> Vec<char> {
+        alternatives_fixed_counts(&self.alternatives)
+            .into_iter()
+            .flat_map(|(c, count)| std::iter::repeat(c).take(count))
+            .collect()
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    strict: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Whether the `$` at the current position ends the pattern
+    fn at_end_anchor(&self) -> bool {
+        self.chars[self.pos + 1..].iter().all(|c| c.is_whitespace())
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Node>>> {
+        let mut alternatives = vec![self.parse_sequence()?];
+
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Node>> {
+        let mut sequence = Vec::new();
+
+        while let Some(ch) = self.peek() {
+            let atom = match ch {
+                '|' | ')' => break,
+                '$' if self.at_end_anchor() => break,
+                '(' => {
+                    let open = self.pos;
+                    self.pos += 1;
+                    let alternatives = self.parse_alternatives()?;
+
+                    if self.peek() != Some(')') {
+                        return Err(Error::UnclosedFilter {
+                            open: '(',
+                            position: open,
+                        });
+                    }
+                    self.pos += 1;
+
+                    Node::Group(alternatives)
+                }
+                '[' => self.parse_class()?,
+                '.' | '-' | '_' | '#' => {
+                    self.pos += 1;
+                    Node::Class(Class::any_letter())
+                }
+                c if c.is_ascii_digit() => {
+                    let run = self.parse_run_length()?;
+                    Node::Repeat {
+                        node: Box::new(Node::Class(Class::any_letter())),
+                        min: run,
+                        max: Some(run),
+                    }
+                }
+                c if c.is_alphabetic() => {
+                    self.pos += 1;
+                    Node::Class(Class::letter(c))
+                }
+                c if c.is_whitespace() || !self.strict => {
+                    self.pos += 1;
+                    continue;
+                }
+                c => {
+                    return Err(Error::InvalidFilter {
+                        ch: c,
+                        position: self.pos,
+                    })
+                }
+            };
+
+            sequence.push(self.parse_repetition(atom));
+        }
+
+        Ok(sequence)
+    }
+
+    fn parse_repetition(&mut self, atom: Node) -> Node {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            _ => return atom,
+        };
+        self.pos += 1;
+
+        Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Node> {
+        let open = self.pos;
+        self.pos += 1;
+
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut letters = Vec::new();
+        loop {
+            match self.peek() {
+                Some(']') => break,
+                Some(c) if c.is_alphabetic() => letters.push(c),
+                Some(c) if self.strict && !c.is_whitespace() => {
+                    return Err(Error::InvalidFilter {
+                        ch: c,
+                        position: self.pos,
+                    })
+                }
+                Some(_) => {}
+                None => {
+                    return Err(Error::UnclosedFilter {
+                        open: '[',
+                        position: open,
+                    })
+                }
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+
+        letters.sort_unstable();
+        letters.dedup();
+
+        Ok(Node::Class(Class { letters, negated }))
+    }
+
+    fn parse_run_length(&mut self) -> Result<usize> {
+        let start = self.pos;
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        let run: String = self.chars[start..self.pos].iter().collect();
+        let num: u8 = run.parse().map_err(|_| Error::InvalidRunLength {
+            run: run.clone(),
+            position: start,
+        })?;
+
+        Ok(num as usize)
+    }
+}
+
+/// Positions in `word` reachable by matching one of `alternatives` from any of the `starts`
+fn match_alternatives(
+    alternatives: &[Vec<Node>],
+    word: &[char],
+    starts: &[bool],
+    fold: &dyn Fn(char) -> char,
+) -> Vec<bool> {
+    let mut ends = vec![false; starts.len()];
+
+    for sequence in alternatives {
+        let seq_ends = sequence.iter().fold(starts.to_vec(), |cur, node| {
+            match_node(node, word, &cur, fold)
+        });
+        for (end, seq_end) in ends.iter_mut().zip(seq_ends) {
+            *end |= seq_end;
+        }
+    }
+
+    return ends;
+}
+
+/// Positions in `word` reachable by matching `node` from any of the `starts`
+fn match_node(
+    node: &Node,
+    word: &[char],
+    starts: &[bool],
+    fold: &dyn Fn(char) -> char,
+) -> Vec<bool> {
+    match node {
+        Node::Class(class) => {
+            let mut ends = vec![false; starts.len()];
+            for (i, &c) in word.iter().enumerate() {
+                ends[i + 1] = starts[i] && class.matches(c, fold);
+            }
+            ends
+        }
+        Node::Group(alternatives) => match_alternatives(alternatives, word, starts, fold),
+        Node::Repeat { node, min, max } => {
+            let mut cur = starts.to_vec();
+            for _ in 0..*min {
+                cur = match_node(node, word, &cur, fold);
+            }
+
+            // keep repeating until `max` or until no new positions can be reached
+            let mut reached = cur.clone();
+            let mut count = *min;
+            while max.map_or(true, |max| count < max) {
+                cur = match_node(node, word, &cur, fold);
+                count += 1;
+
+                let mut found_new = false;
+                for (r, &c) in reached.iter_mut().zip(cur.iter()) {
+                    found_new |= c && !*r;
+                    *r |= c;
+                }
+                if !found_new {
+                    break;
+                }
+            }
+
+            reached
+        }
+    }
+}
+
+fn alternatives_length_range(alternatives: &[Vec<Node>]) -> (usize, Option<usize>) {
+    alternatives
+        .iter()
+        .map(|sequence| {
+            sequence
+                .iter()
+                .map(node_length_range)
+                .fold((0, Some(0)), |(min, max), (n_min, n_max)| {
+                    (min + n_min, max.and_then(|m| n_max.map(|n| m + n)))
+                })
+        })
+        .fold((usize::MAX, Some(0)), |(min, max), (s_min, s_max)| {
+            (min.min(s_min), max.and_then(|m| s_max.map(|s| m.max(s))))
+        })
+}
+
+fn node_length_range(node: &Node) -> (usize, Option<usize>) {
+    match node {
+        Node::Class(_) => (1, Some(1)),
+        Node::Group(alternatives) => alternatives_length_range(alternatives),
+        Node::Repeat { node, min, max } => {
+            let (n_min, n_max) = node_length_range(node);
+            (
+                n_min * min,
+                max.and_then(|max| n_max.map(|n_max| n_max * max)),
+            )
+        }
+    }
+}
+
+/// How often each letter occurs in every match of one of `alternatives`
+fn alternatives_fixed_counts(alternatives: &[Vec<Node>]) -> BTreeMap<char, usize> {
+    alternatives
+        .iter()
+        .map(|sequence| {
+            sequence
+                .iter()
+                .map(node_fixed_counts)
+                .fold(BTreeMap::new(), |mut acc, counts| {
+                    for (c, count) in counts {
+                        *acc.entry(c).or_insert(0) += count;
+                    }
+                    acc
+                })
+        })
+        .fold(
+            None,
+            |acc: Option<BTreeMap<char, usize>>, counts| match acc {
+                None => Some(counts),
+                // only letters every alternative has
+                Some(acc) => Some(
+                    acc.into_iter()
+                        .filter_map(|(c, a)| counts.get(&c).map(|&count| (c, a.min(count))))
+                        .collect(),
+                ),
+            },
+        )
+        .unwrap_or_default()
+}
+
+fn node_fixed_counts(node: &Node) -> BTreeMap<char, usize> {
+    match node {
+        Node::Class(class) => class.single_letter().map(|c| (c, 1)).into_iter().collect(),
+        Node::Group(alternatives) => alternatives_fixed_counts(alternatives),
+        Node::Repeat { node, min, .. } => {
+            let mut counts = node_fixed_counts(node);
+            counts.values_mut().for_each(|c| *c *= min);
+            counts
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Filter;
+
+    fn matches(pattern: &str, word: &str) -> bool {
+        Pattern::parse(pattern, true).unwrap().matches(word)
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(matches("c[aeiou]t", "cat"));
+        assert!(matches("c[aeiou]t", "cut"));
+        assert!(!matches("c[aeiou]t", "cyt"));
+        assert!(matches("[^s]..", "cat"));
+        assert!(!matches("[^s]..", "sat"));
+        assert!(!matches("c[aeiou]t", "cats"));
+    }
+
+    #[test]
+    fn test_anchors() {
+        assert!(matches("(ing|ed)$", "sing"));
+        assert!(matches("(ing|ed)$", "baked"));
+        assert!(!matches("(ing|ed)$", "singer"));
+        assert!(matches("^un", "undo"));
+        assert!(!matches("^un", "fun"));
+        assert!(matches("^a.e$", "ate"));
+    }
+
+    #[test]
+    fn test_repetition() {
+        assert!(matches("ca*t", "ct"));
+        assert!(matches("ca*t", "caaat"));
+        assert!(!matches("ca+t", "ct"));
+        assert!(matches("colou?r", "color"));
+        assert!(matches("colou?r", "colour"));
+        assert!(matches("s2(ing)?", "sat"));
+        assert!(matches("s2(ing)?", "sating"));
+        assert!(matches("(a|)*b", "aab"));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let pattern = Pattern::parse("s[aeiou]_(ed|s)", true).unwrap();
+        assert_eq!(pattern.length_range(), (4, Some(5)));
+        assert_eq!(pattern.fixed_letters(), vec!['s']);
+
+        let pattern = Pattern::parse("(ing|ed)$", true).unwrap();
+        assert_eq!(pattern.length_range(), (2, None));
+        assert_eq!(pattern.fixed_letters(), vec![]);
+
+        let pattern = Pattern::parse("a+(bb)*", true).unwrap();
+        assert_eq!(pattern.length_range(), (1, None));
+        assert_eq!(pattern.fixed_letters(), vec!['a']);
+    }
+
+    #[test]
+    fn test_non_english_letters() {
+        assert!(matches("ca[ñn]o", "caño"));
+        assert!(matches("[^ñ]+o", "gato"));
+        assert!(!matches("[^ñ]+o", "caño"));
+        assert!(matches("^ő", "őrült"));
+        assert_eq!(
+            Pattern::parse("ü(ß|ss)e", true).unwrap().fixed_letters(),
+            vec!['e', 'ü']
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Pattern::parse("(ab", true).is_err());
+        assert!(Pattern::parse("[ab", true).is_err());
+        assert!(Pattern::parse("ab)", true).is_err());
+        assert!(Pattern::parse("*a", true).is_err());
+        assert!(Pattern::parse("*a", false).is_ok());
+        // positions count the marker of a filter
+        assert!(matches!(
+            Pattern::parse("/ab)", true),
+            Err(Error::InvalidFilter {
+                ch: ')',
+                position: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_marked_filters() {
+        assert_eq!(
+            Filter::parse("/c[aeiou]t").unwrap(),
+            Filter::Pattern(Pattern::parse("c[aeiou]t", true).unwrap())
+        );
+        assert!(Filter::parse("/(ing|ed)$").unwrap().matches("baked"));
+
+        // without the marker, pattern characters mean nothing like they always did
+        assert_eq!(Filter::new("a??"), Filter::ExactString(vec!['a']));
+        assert_eq!(Filter::new("c.t*"), Filter::ExactString(vec!['c', 't']));
+        assert!(matches!(
+            Filter::parse("a??"),
+            Err(Error::InvalidFilter {
+                ch: '?',
+                position: 1
+            })
+        ));
+        assert!(Filter::parse("c[aeiou]t").is_err());
+    }
+}
@@ -7,45 +7,184 @@
 
 use wasm_bindgen::prelude::*;
 
+pub use alphabet::*;
 pub use board_solver::*;
+pub use error::*;
 pub use filter::*;
+pub use lookup_options::*;
+pub use lookup_stats::*;
+pub use normalization::*;
+pub use ranking::*;
 pub use word::*;
+pub use word_delta::*;
 pub use word_searcher::*;
+pub use wordlist::*;
 
+mod alphabet;
 mod board_solver;
+mod error;
 mod filter;
+mod lookup_options;
+mod lookup_stats;
+mod normalization;
+mod ranking;
 mod word;
+mod word_delta;
 mod word_searcher;
+mod wordlist;
 
+/// Name of the dictionary embedded at compile time, which is always loaded at first
+const DEFAULT_DICTIONARY: &str = "default";
+
+/// Several dictionaries loaded at once, one of which is used for lookups
+///
+/// Lookup indices laid out like "dag.bin" (including the embedded one) are searched in place, while
+/// wordlists are built into a `DAGSearcher`.
 #[wasm_bindgen]
-pub struct WordSearcherWrapper(DAGSearcher);
+pub struct WordSearcherWrapper {
+    dictionaries: std::collections::HashMap<String, Box<dyn WordSearcher>>,
+    selected: String,
+}
 
 #[wasm_bindgen]
 impl WordSearcherWrapper {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        Self(DAGSearcher::default())
+        let mut dictionaries = std::collections::HashMap::new();
+        let default: Box<dyn WordSearcher> = Box::new(DAGIndex::embedded());
+        dictionaries.insert(DEFAULT_DICTIONARY.to_string(), default);
+
+        Self {
+            dictionaries,
+            selected: DEFAULT_DICTIONARY.to_string(),
+        }
+    }
+
+    /// Load a lookup index laid out like "dag.bin" as dictionary `name`, replacing any dictionary
+    /// of the same name
+    pub fn load_dag(&mut self, name: &str, bytes: &[u8]) -> Result<(), JsValue> {
+        let searcher = DAGIndex::try_from_bytes(bytes.to_vec()).map_err(to_js_error)?;
+        self.dictionaries
+            .insert(name.to_string(), Box::new(searcher));
+        Ok(())
+    }
+
+    /// Build a lookup index from the contents of a wordlist file in the built-in `alphabet` (like
+    /// "english" or "spanish") as dictionary `name`, replacing any dictionary of the same name
+    pub fn load_wordlist(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+        alphabet: &str,
+    ) -> Result<(), JsValue> {
+        let searcher = Alphabet::from_name(alphabet)
+            .and_then(|alphabet| {
+                DAGSearcher::try_from_wordlist(Wordlist::from_bytes(bytes).with_alphabet(alphabet))
+            })
+            .map_err(to_js_error)?;
+        self.dictionaries
+            .insert(name.to_string(), Box::new(searcher));
+        Ok(())
+    }
+
+    /// Use dictionary `name` for all following lookups
+    pub fn select(&mut self, name: &str) -> Result<(), JsValue> {
+        if !self.dictionaries.contains_key(name) {
+            return Err(to_js_error(Error::UnknownDictionary(name.to_string())));
+        }
+        self.selected = name.to_string();
+        Ok(())
+    }
+
+    /// Unload dictionary `name`, going back to the default dictionary if it was selected
+    pub fn remove(&mut self, name: &str) -> Result<(), JsValue> {
+        if name == DEFAULT_DICTIONARY || self.dictionaries.remove(name).is_none() {
+            return Err(to_js_error(Error::UnknownDictionary(name.to_string())));
+        }
+        if self.selected == name {
+            self.selected = DEFAULT_DICTIONARY.to_string();
+        }
+        Ok(())
+    }
+
+    /// Names of all loaded dictionaries, one per line
+    pub fn dictionaries(&self) -> String {
+        let mut names: Vec<_> = self.dictionaries.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names.join("\n")
+    }
+
+    pub fn selected(&self) -> String {
+        self.selected.clone()
     }
 
-    pub fn lookup(&self, s: &str) -> String {
-        let mut words = self.0.lookup(s);
-        words.sort_unstable_by(|a, b| {
-            b.len()
-                .cmp(&a.len())
-                .then(b.frequency().cmp(&a.frequency()).then_with(|| a.cmp(&b)))
-        });
-        words.join("\n")
+    pub fn lookup(&self, s: &str) -> Result<String, JsValue> {
+        let words = self.searcher().try_lookup(s).map_err(to_js_error)?;
+        format_words(words, s, self.searcher().alphabet()).map_err(to_js_error)
     }
 
-    pub fn lookup_filter(&self, s: &str, filter: &str) -> String {
-        let mut words = self.0.lookup_filter(s, filter);
-        words.sort_unstable_by(|a, b| {
-            b.len()
-                .cmp(&a.len())
-                .then(b.frequency().cmp(&a.frequency()).then_with(|| a.cmp(&b)))
-        });
-        words.join("\n")
+    /// Like `lookup`, but only the `k` longest and most frequent words
+    pub fn lookup_top_k(&self, s: &str, k: usize) -> Result<String, JsValue> {
+        let words = self
+            .searcher()
+            .try_lookup_top_k(s, k, Ranking::LengthThenFrequency)
+            .map_err(to_js_error)?;
+        format_words(words, s, self.searcher().alphabet()).map_err(to_js_error)
     }
+
+    pub fn lookup_filter(&self, s: &str, filter: &str) -> Result<String, JsValue> {
+        let words = self
+            .searcher()
+            .try_lookup_filter(s, filter)
+            .map_err(to_js_error)?;
+        format_words(words, s, self.searcher().alphabet()).map_err(to_js_error)
+    }
+
+    /// Like `lookup_filter`, but only words containing all `required` letters and none of the
+    /// `banned` ones
+    pub fn lookup_with(
+        &self,
+        s: &str,
+        filter: &str,
+        required: &str,
+        banned: &str,
+    ) -> Result<String, JsValue> {
+        let words = LookupOptions::try_new(required, banned)
+            .and_then(|options| self.searcher().try_lookup_with(s, filter, &options))
+            .map_err(to_js_error)?;
+        format_words(words, s, self.searcher().alphabet()).map_err(to_js_error)
+    }
+}
+
+impl WordSearcherWrapper {
+    fn searcher(&self) -> &dyn WordSearcher {
+        &*self.dictionaries[&self.selected]
+    }
+}
+
+fn to_js_error(e: Error) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// Sort words longest and most frequent first, one per line, noting which letters blanks stood for
+fn format_words(mut words: Vec<Word>, letters: &str, alphabet: &Alphabet) -> Result<String> {
+    Ranking::LengthThenFrequency.sort(&mut words);
+
+    let letters = alphabet.try_to_set(letters)?;
+
+    let lines = words
+        .iter()
+        .map(|w| {
+            let filled = alphabet.try_to_set(w)?.difference(&letters);
+            if filled.is_empty() {
+                Ok(w.to_string())
+            } else {
+                Ok(format!("{} (? = {})", w, alphabet.set_to_string(&filled)))
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(lines.join("\n"))
 }
 
 #[wasm_bindgen]
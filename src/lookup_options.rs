@@ -0,0 +1,108 @@
+use crate::{
+    alphabet::{is_blank, lowercase},
+    AlphaMultiset, Alphabet, Error, Result,
+};
+
+/// Letter constraints on looked up words, which searchers use to prune their index
+///
+/// Letters are kept as they are given, and only mapped onto the alphabet of a searcher when it
+/// looks them up.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct LookupOptions {
+    /// letters every word must contain, as many times as they occur here, in sorted order
+    pub required: Vec<char>,
+    /// letters no word may contain, no matter how often they occur here, in sorted order
+    pub banned: Vec<char>,
+}
+
+impl LookupOptions {
+    pub fn new(required: &str, banned: &str) -> Self {
+        Self::try_new(required, banned).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but reports letters which can't be required or banned instead of panicking
+    pub fn try_new(required: &str, banned: &str) -> Result<Self> {
+        Ok(Self {
+            required: try_parse_letters(required)?,
+            banned: try_parse_letters(banned)?,
+        })
+    }
+
+    /// Whether the letters of `word`, in `alphabet`, meet these constraints
+    ///
+    /// Letters are folded like looked up ones, so a required `á` is met by an `a` in Spanish.
+    pub fn matches(&self, word: &str, alphabet: &Alphabet) -> bool {
+        match (alphabet.try_to_set(word), self.required_set(alphabet)) {
+            (Ok(letters), Ok(required)) => {
+                letters.has_subset(&required) && letters.is_disjoint(&self.banned_set(alphabet))
+            }
+            // no word of the alphabet has letters outside of it
+            _ => false,
+        }
+    }
+
+    /// The required letters in `alphabet`, reporting letters outside of it or too many
+    /// repetitions of one
+    pub(crate) fn required_set(&self, alphabet: &Alphabet) -> Result<AlphaMultiset> {
+        alphabet.try_chars_to_set(self.required.iter().copied())
+    }
+
+    /// The banned letters in `alphabet`, once each, skipping letters outside of it which can't be in
+    /// any word anyway
+    pub(crate) fn banned_set(&self, alphabet: &Alphabet) -> AlphaMultiset {
+        let mut banned: Vec<_> = self
+            .banned
+            .iter()
+            .copied()
+            .filter(|&c| alphabet.contains(c))
+            .map(|c| alphabet.fold(c))
+            .collect();
+        banned.sort_unstable();
+        banned.dedup();
+
+        alphabet
+            .try_chars_to_set(banned)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// Collect the letters of `s`, skipping separators like spaces or commas
+fn try_parse_letters(s: &str) -> Result<Vec<char>> {
+    let mut letters = Vec::new();
+
+    for c in s.chars().filter(|c| !c.is_whitespace() && *c != ',') {
+        // blanks only make sense in looked up letters
+        if is_blank(c) || !c.is_alphabetic() {
+            return Err(Error::InvalidChar(c));
+        }
+        letters.push(lowercase(c));
+    }
+    letters.sort_unstable();
+
+    return Ok(letters);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_matches() {
+        let options = LookupOptions::new("ee", "s, t");
+
+        let english = Alphabet::english();
+
+        assert!(options.matches("green", &english));
+        assert!(!options.matches("red", &english));
+        assert!(!options.matches("steep", &english));
+        assert!(LookupOptions::try_new("e?", "").is_err());
+        assert!(!LookupOptions::new("ñ", "").matches("ano", &english));
+        assert!(LookupOptions::new("sss", "").matches("stresses", &english));
+
+        let spanish = Alphabet::spanish();
+        assert!(LookupOptions::new("ñ", "").matches("año", &spanish));
+        assert!(LookupOptions::new("ó", "").matches("cancion", &spanish));
+        assert!(!LookupOptions::new("", "á").matches("cancion", &spanish));
+        assert!(LookupOptions::new("", "ááááááááááááááááá").matches("tu", &spanish));
+    }
+}
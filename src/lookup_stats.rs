@@ -0,0 +1,61 @@
+use crate::Word;
+use std::time::Duration;
+
+/// How much work a lookup took, to tell why it's slow
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct LookupStats {
+    /// steps the searcher took, whatever a step is for it: nodes visited by `DAGSearcher` and
+    /// `DAGIndex`, trie nodes by `TrieSearcher`, states by `AutomatonSearcher` and `FstSearcher`,
+    /// subsets probed by `ExpSearcher`, or sets checked by `SimpleSearcher` and `ColumnarSearcher`,
+    /// or none for searchers which don't count their steps
+    pub steps: Option<usize>,
+    /// words found
+    pub found: usize,
+    /// time the lookup took, which WASM builds can't measure
+    pub elapsed: Option<Duration>,
+}
+
+impl LookupStats {
+    /// Time `lookup`, which returns the words it found along with the steps it took
+    pub fn measure<F: FnOnce() -> (Vec<Word>, usize)>(lookup: F) -> (Vec<Word>, Self) {
+        Self::time(|| {
+            let (words, steps) = lookup();
+            (words, Some(steps))
+        })
+    }
+
+    /// Time `lookup` of a searcher which doesn't count its steps
+    pub fn measure_uncounted<F: FnOnce() -> Vec<Word>>(lookup: F) -> (Vec<Word>, Self) {
+        Self::time(|| (lookup(), None))
+    }
+
+    fn time<F: FnOnce() -> (Vec<Word>, Option<usize>)>(lookup: F) -> (Vec<Word>, Self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let start = std::time::Instant::now();
+        let (words, steps) = lookup();
+        #[cfg(not(target_arch = "wasm32"))]
+        let elapsed = Some(start.elapsed());
+        #[cfg(target_arch = "wasm32")]
+        let elapsed = None;
+
+        let stats = Self {
+            steps,
+            found: words.len(),
+            elapsed,
+        };
+        (words, stats)
+    }
+}
+
+impl std::fmt::Display for LookupStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} words", self.found)?;
+        if let Some(steps) = self.steps {
+            write!(f, " in {} steps", steps)?;
+        }
+        if let Some(elapsed) = self.elapsed {
+            write!(f, ", {:?}", elapsed)?;
+        }
+        Ok(())
+    }
+}
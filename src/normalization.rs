@@ -0,0 +1,173 @@
+use crate::{Alphabet, Error, Result};
+
+/// What to do with a word containing a certain kind of character
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum CharHandling {
+    /// Remove the character and keep the rest of the word, so "can't" becomes "cant"
+    Strip,
+    /// Leave the whole word out of the index
+    Drop,
+    /// Fail to build the index
+    Reject,
+}
+
+/// How the words of a wordlist are turned into the words of an index
+///
+/// The default suits Wordscapes, which only uses words of 3 or more letters.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub struct NormalizationPolicy {
+    /// Shortest word (in letters, after normalization) to keep
+    pub min_len: usize,
+    /// Longest word (in letters, after normalization) to keep
+    pub max_len: usize,
+    /// Handling of apostrophes and hyphens
+    pub punctuation: CharHandling,
+    /// Handling of any other character which isn't a letter of the alphabet, like digits or
+    /// accented letters which don't fold onto one
+    pub other_chars: CharHandling,
+    /// Whether to lowercase capitals, or otherwise drop words which contain them (like most
+    /// proper nouns in a mixed case wordlist)
+    pub fold_case: bool,
+}
+
+impl Default for NormalizationPolicy {
+    fn default() -> Self {
+        Self::wordscapes()
+    }
+}
+
+impl NormalizationPolicy {
+    /// Words of 3 or more letters, with any other characters stripped
+    pub fn wordscapes() -> Self {
+        Self {
+            min_len: 3,
+            max_len: usize::MAX,
+            punctuation: CharHandling::Strip,
+            other_chars: CharHandling::Strip,
+            fold_case: true,
+        }
+    }
+
+    /// Words of 2 to 15 letters, without contractions, hyphenated words or anything else which
+    /// can't be played as is
+    pub fn scrabble() -> Self {
+        Self {
+            min_len: 2,
+            max_len: 15,
+            punctuation: CharHandling::Drop,
+            other_chars: CharHandling::Drop,
+            fold_case: true,
+        }
+    }
+
+    /// Normalize a word into lowercase (and folded) letters of `alphabet`, or `None` if it's left
+    /// out of the index
+    pub fn normalize(&self, word: &str, alphabet: &Alphabet) -> Result<Option<String>> {
+        let mut normalized = String::with_capacity(word.len());
+        let mut len = 0;
+
+        for c in word.chars() {
+            let handling = if alphabet.contains(c) {
+                if c.is_uppercase() && !self.fold_case {
+                    return Ok(None);
+                }
+                normalized.push(alphabet.fold(c));
+                len += 1;
+                continue;
+            } else if is_punctuation(c) {
+                self.punctuation
+            } else {
+                self.other_chars
+            };
+
+            match handling {
+                CharHandling::Strip => {}
+                CharHandling::Drop => return Ok(None),
+                CharHandling::Reject => return Err(Error::InvalidChar(c)),
+            }
+        }
+
+        if len < self.min_len || len > self.max_len {
+            return Ok(None);
+        }
+
+        return Ok(Some(normalized));
+    }
+}
+
+/// Apostrophes (straight or curly) and hyphens
+fn is_punctuation(c: char) -> bool {
+    c == '\'' || c == '\u{2019}' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SimpleSearcher, WordSearcher, Wordlist};
+
+    #[test]
+    fn test_normalize() {
+        let english = Alphabet::english();
+        let wordscapes = NormalizationPolicy::wordscapes();
+        assert_eq!(
+            wordscapes.normalize("Can't", &english).unwrap(),
+            Some("cant".to_string())
+        );
+        assert_eq!(wordscapes.normalize("at", &english).unwrap(), None);
+
+        let scrabble = NormalizationPolicy::scrabble();
+        assert_eq!(
+            scrabble.normalize("AT", &english).unwrap(),
+            Some("at".to_string())
+        );
+        assert_eq!(scrabble.normalize("can't", &english).unwrap(), None);
+        assert_eq!(scrabble.normalize("x-ray", &english).unwrap(), None);
+
+        let strict = NormalizationPolicy {
+            other_chars: CharHandling::Reject,
+            fold_case: false,
+            ..NormalizationPolicy::default()
+        };
+        assert_eq!(strict.normalize("Paris", &english).unwrap(), None);
+        assert!(matches!(
+            strict.normalize("mp3s", &english),
+            Err(Error::InvalidChar('3'))
+        ));
+
+        let spanish = Alphabet::spanish();
+        assert_eq!(
+            scrabble.normalize("Canción", &spanish).unwrap(),
+            Some("cancion".to_string())
+        );
+        assert_eq!(scrabble.normalize("niño", &english).unwrap(), None);
+        assert_eq!(
+            wordscapes.normalize("niño", &spanish).unwrap(),
+            Some("niño".to_string())
+        );
+    }
+
+    #[test]
+    fn test_policy_reaches_index() {
+        let bytes = b"at 10\ncan't 5\ncat 3\n";
+
+        let wordscapes = SimpleSearcher::from_wordlist(Wordlist::from_bytes(bytes));
+        let mut words = wordscapes
+            .lookup("cant")
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>();
+        words.sort();
+        assert_eq!(words, vec!["cant", "cat"]);
+
+        let scrabble = SimpleSearcher::from_wordlist(
+            Wordlist::from_bytes(bytes).with_normalization(NormalizationPolicy::scrabble()),
+        );
+        let mut words = scrabble
+            .lookup("cant")
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>();
+        words.sort();
+        assert_eq!(words, vec!["at", "cat"]);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Word, WordRef};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Orders in which looked up words can be ranked, best first
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum Ranking {
+    /// Most frequent words first
+    Frequency,
+    /// Longest words first, most frequent first among words of the same length
+    LengthThenFrequency,
+    /// Alphabetical order
+    Alphabetical,
+}
+
+impl Default for Ranking {
+    fn default() -> Self {
+        Ranking::LengthThenFrequency
+    }
+}
+
+impl Ranking {
+    /// Compare two words, where the better ranked word is `Ordering::Less`
+    ///
+    /// Ties are broken alphabetically, and then by frequency
+    pub fn compare(self, a: WordRef, b: WordRef) -> Ordering {
+        let alphabetical = || {
+            a.as_str()
+                .cmp(b.as_str())
+                .then(b.frequency().cmp(&a.frequency()))
+        };
+
+        match self {
+            Ranking::Frequency => b.frequency().cmp(&a.frequency()).then_with(alphabetical),
+            Ranking::LengthThenFrequency => b
+                .chars()
+                .count()
+                .cmp(&a.chars().count())
+                .then(b.frequency().cmp(&a.frequency()))
+                .then_with(alphabetical),
+            Ranking::Alphabetical => alphabetical(),
+        }
+    }
+
+    /// Sort words so the best ranked word comes first
+    pub fn sort(self, words: &mut [Word]) {
+        words.sort_unstable_by(|a, b| self.compare(a.into(), b.into()));
+    }
+}
+
+/// Collect the best `k` words, best first, without sorting all of them
+pub(crate) fn top_k<'a, I>(words: I, k: usize, ranking: Ranking) -> Vec<Word>
+where
+    I: Iterator<Item = WordRef<'a>>,
+{
+    let mut best = TopK::new(k, ranking);
+    words.for_each(|w| best.push(w));
+
+    return best.into_sorted_vec();
+}
+
+/// Bounded max-heap holding the best `k` words seen so far, with the worst of them on top
+pub(crate) struct TopK<'a> {
+    k: usize,
+    ranking: Ranking,
+    heap: BinaryHeap<Ranked<'a>>,
+}
+
+impl<'a> TopK<'a> {
+    pub fn new(k: usize, ranking: Ranking) -> Self {
+        Self {
+            k,
+            ranking,
+            heap: BinaryHeap::with_capacity(k.saturating_add(1).min(1024)),
+        }
+    }
+
+    pub fn push(&mut self, word: WordRef<'a>) {
+        if self.k == 0 {
+            return;
+        }
+
+        if self.heap.len() == self.k {
+            // only replace the worst word if `word` is better
+            let mut worst = self.heap.peek_mut().unwrap();
+            if self.ranking.compare(word, worst.0) == Ordering::Less {
+                *worst = Ranked(word, self.ranking);
+            }
+        } else {
+            self.heap.push(Ranked(word, self.ranking));
+        }
+    }
+
+    /// The worst of the best `k` words, if `k` words have been found already
+    pub fn worst(&self) -> Option<WordRef<'a>> {
+        if self.heap.len() == self.k {
+            self.heap.peek().map(|r| r.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_sorted_vec(self) -> Vec<Word> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|r| r.0.to_word())
+            .collect()
+    }
+}
+
+/// Word ordered by a ranking, so that worse words compare greater
+struct Ranked<'a>(WordRef<'a>, Ranking);
+
+impl PartialEq for Ranked<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked<'_> {}
+
+impl PartialOrd for Ranked<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.1.compare(self.0, other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k() {
+        let words = vec![
+            Word::from_pair("bat".to_string(), 10),
+            Word::from_pair("tab".to_string(), 30),
+            Word::from_pair("abate".to_string(), 5),
+            Word::from_pair("beat".to_string(), 30),
+            Word::from_pair("eat".to_string(), 20),
+        ];
+        let refs = || words.iter().map(WordRef::from);
+        let strs = |ws: Vec<Word>| ws.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            strs(top_k(refs(), 3, Ranking::Frequency)),
+            vec!["beat", "tab", "eat"]
+        );
+        assert_eq!(
+            strs(top_k(refs(), 3, Ranking::LengthThenFrequency)),
+            vec!["abate", "beat", "tab"]
+        );
+        assert_eq!(
+            strs(top_k(refs(), 2, Ranking::Alphabetical)),
+            vec!["abate", "bat"]
+        );
+        assert_eq!(top_k(refs(), 10, Ranking::Frequency).len(), 5);
+        assert!(top_k(refs(), 0, Ranking::Frequency).is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Clone, Serialize, Deserialize)]
@@ -9,17 +10,22 @@ impl Word {
     }
 
     pub fn from_freqlist_line(line: &str) -> Self {
+        Self::try_from_freqlist_line(line).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parse a line of the form `word<whitespace>frequency`
+    pub fn try_from_freqlist_line(line: &str) -> Result<Self> {
         let mut words = line.split_ascii_whitespace();
-        let (word, freq) = (
-            words.next().expect("Failed to get word from line"),
-            words.next().expect("Failed to get freq from line"),
-        );
+        let (word, freq) = match (words.next(), words.next()) {
+            (Some(word), Some(freq)) => (word, freq),
+            _ => return Err(Error::MalformedLine(line.to_string())),
+        };
 
-        Self(
+        Ok(Self(
             word.to_string(),
             freq.parse()
-                .unwrap_or_else(|_| panic!("Failed to parse {} as usize", freq)),
-        )
+                .map_err(|_| Error::MalformedLine(line.to_string()))?,
+        ))
     }
 
     pub fn frequency(&self) -> usize {
@@ -45,3 +51,51 @@ impl std::borrow::Borrow<str> for Word {
         self.0.as_str()
     }
 }
+
+/// A `Word` borrowed from a lookup index, so lookups don't have to copy every word they find
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Clone, Copy)]
+pub struct WordRef<'a>(&'a str, usize);
+
+impl<'a> WordRef<'a> {
+    pub fn from_pair(word: &'a str, freq: usize) -> Self {
+        Self(word, freq)
+    }
+
+    pub fn frequency(&self) -> usize {
+        self.1
+    }
+
+    /// Get the word itself, for as long as the index it was borrowed from
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    pub fn to_word(&self) -> Word {
+        Word(self.0.to_string(), self.1)
+    }
+}
+
+impl<'a> std::convert::From<&'a Word> for WordRef<'a> {
+    fn from(word: &'a Word) -> Self {
+        Self(&*word.0, word.1)
+    }
+}
+
+impl<'a> std::convert::From<WordRef<'a>> for Word {
+    fn from(word: WordRef<'a>) -> Self {
+        word.to_word()
+    }
+}
+
+impl<'a> std::fmt::Display for WordRef<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> std::ops::Deref for WordRef<'a> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Error, Result, Word};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Words added to or removed from a lookup index after it was built, which can be saved and applied
+/// again to a freshly built index with `EditableWordSearcher::apply`
+///
+/// Only the last edit of a word is kept. A saved delta has an edit per line, `+word frequency` for
+/// added words and `-word` for removed ones, so lists of words kept by hand are easy to turn into
+/// one.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WordDelta {
+    /// frequency of each added word, or `None` for removed ones
+    edits: BTreeMap<String, Option<usize>>,
+}
+
+impl WordDelta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record adding `word` with `frequency`, replacing any earlier edit of it
+    pub fn insert(&mut self, word: &str, frequency: usize) {
+        self.edits.insert(word.to_string(), Some(frequency));
+    }
+
+    /// Record removing `word`, replacing any earlier edit of it
+    pub fn remove(&mut self, word: &str) {
+        self.edits.insert(word.to_string(), None);
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// The edited words in sorted order, with the frequency of added words or `None` for removed
+    /// ones
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<usize>)> {
+        self.edits
+            .iter()
+            .map(|(word, &frequency)| (&**word, frequency))
+    }
+
+    /// Read a saved delta, skipping empty lines
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut delta = Self::new();
+
+        for (i, line) in String::from_utf8_lossy(bytes).lines().enumerate() {
+            delta
+                .try_add_line(line.trim())
+                .map_err(|e| e.at_line(i + 1))?;
+        }
+
+        return Ok(delta);
+    }
+
+    /// Read a delta saved at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| Error::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Self::try_from_bytes(&bytes)
+    }
+
+    /// Save this delta with an edit per line, in the order of `iter`
+    pub fn write<W: std::io::Write>(&self, mut out: W) -> Result<()> {
+        for (word, frequency) in self.iter() {
+            match frequency {
+                Some(frequency) => writeln!(out, "+{} {}", word, frequency)?,
+                None => writeln!(out, "-{}", word)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Save this delta to `path`, replacing the file if it exists
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path).map_err(|source| Error::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        self.write(std::io::BufWriter::new(file))
+    }
+
+    fn try_add_line(&mut self, line: &str) -> Result<()> {
+        let malformed = || Error::MalformedLine(line.to_string());
+
+        if line.is_empty() {
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix('+') {
+            let word = Word::try_from_freqlist_line(rest).map_err(|_| malformed())?;
+            self.insert(&word, word.frequency());
+        } else if let Some(rest) = line.strip_prefix('-') {
+            let mut words = rest.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(word), None) => self.remove(word),
+                _ => return Err(malformed()),
+            }
+        } else {
+            return Err(malformed());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_round_trip() {
+        let mut delta = WordDelta::new();
+        delta.insert("qi", 10);
+        delta.remove("zzz");
+        delta.insert("zzz", 3);
+        delta.remove("aaa");
+
+        let mut saved = Vec::new();
+        delta.write(&mut saved).unwrap();
+        assert_eq!(saved, b"-aaa\n+qi 10\n+zzz 3\n");
+        assert_eq!(WordDelta::try_from_bytes(&saved).unwrap(), delta);
+
+        assert!(matches!(
+            WordDelta::try_from_bytes(b"+qi 10\n\nqi\n"),
+            Err(Error::Wordlist { line: 3, .. })
+        ));
+        assert!(WordDelta::try_from_bytes(b"-two words").is_err());
+    }
+}
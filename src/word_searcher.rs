@@ -1,100 +1,341 @@
-use crate::{Filter, Word};
+use crate::{
+    ranking::top_k, Alphabet, CharHandling, Filter, LookupOptions, LookupStats,
+    NormalizationPolicy, Ranking, Result, Word, WordDelta, WordRef, Wordlist,
+};
 use std::collections::HashMap;
 
 mod alpha_multiset;
 
 mod automaton_searcher;
+mod columnar_searcher;
+mod dag_index;
 mod dag_searcher;
 mod exp_searcher;
+mod fst_searcher;
+mod hybrid_searcher;
+mod set_automaton;
 mod simple_searcher;
 mod trie_searcher;
 
 pub use alpha_multiset::*;
 
 pub use automaton_searcher::*;
+pub use columnar_searcher::*;
+pub use dag_index::*;
 pub use dag_searcher::*;
 pub use exp_searcher::*;
+pub use fst_searcher::*;
+pub use hybrid_searcher::*;
 pub use simple_searcher::*;
 pub use trie_searcher::*;
 
-static EMBEDDED_WORDLIST: &[u8] = include_bytes!("freq_200k.txt");
+pub(crate) static EMBEDDED_WORDLIST: &[u8] = include_bytes!("freq_200k.txt");
+/// `Wordlist::checksum` of the embedded wordlist, computed by the build script
+pub(crate) const EMBEDDED_WORDLIST_CHECKSUM: u32 =
+    include!(concat!(env!("OUT_DIR"), "/wordlist_checksum.rs"));
 
 pub trait WordSearcher {
-    fn lookup(&self, word: &str) -> Vec<Word>;
+    /// Lazily look up words which can be made from `word`, borrowing them from the index
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a>;
+
+    /// The alphabet of the words in the index, which looked up letters have to be part of
+    fn alphabet(&self) -> &Alphabet;
+
+    fn lookup(&self, word: &str) -> Vec<Word> {
+        self.lookup_iter(word).map(Word::from).collect()
+    }
+
+    /// Like `lookup`, along with how much work the lookup took
+    ///
+    /// Searchers which don't count their steps report none.
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure_uncounted(|| self.lookup(word))
+    }
+
+    /// Like `lookup`, but reports letters which can't be looked up instead of panicking
+    fn try_lookup(&self, word: &str) -> Result<Vec<Word>> {
+        self.alphabet().try_to_set(word)?;
+
+        Ok(self.lookup(word))
+    }
+
+    /// Lazily look up words which can be made from `word` and match `filter`
+    fn lookup_iter_filter<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        self.lookup_iter_with(word, filter, &LookupOptions::default())
+    }
+
+    /// Lazily look up words which can be made from `word`, match `filter` and meet the letter
+    /// constraints of `options`
+    ///
+    /// Searchers should use the filter's length and the required and banned letters to skip words
+    /// early where they can
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let filter = filter.clone();
+        let options = options.clone();
+        let alphabet = self.alphabet();
+
+        Box::new(
+            self.lookup_iter(word)
+                .filter(move |w| filter.matches_in(w, alphabet) && options.matches(w, alphabet)),
+        )
+    }
 
     fn lookup_filter(&self, word: &str, filter: &str) -> Vec<Word> {
-        let lookup = self.lookup(word);
+        if filter.is_empty() {
+            self.lookup(word)
+        } else {
+            self.lookup_iter_filter(word, &Filter::new(filter))
+                .map(Word::from)
+                .collect()
+        }
+    }
+
+    /// Like `lookup_filter`, but reports bad letters or filters instead of panicking
+    fn try_lookup_filter(&self, word: &str, filter: &str) -> Result<Vec<Word>> {
+        self.alphabet().try_to_set(word)?;
 
         if filter.is_empty() {
-            lookup
+            Ok(self.lookup(word))
+        } else {
+            Ok(self
+                .lookup_iter_filter(word, &Filter::parse(filter)?)
+                .map(Word::from)
+                .collect())
+        }
+    }
+
+    /// Look up words which can be made from `word`, match `filter` (unless it's empty) and meet the
+    /// letter constraints of `options`
+    fn lookup_with(&self, word: &str, filter: &str, options: &LookupOptions) -> Vec<Word> {
+        let filter = if filter.is_empty() {
+            Filter::Permissive
         } else {
-            let filter = Filter::new(filter);
+            Filter::new(filter)
+        };
 
-            lookup
-                .into_iter()
-                .filter(|word| filter.matches(word))
-                .collect()
+        self.lookup_iter_with(word, &filter, options)
+            .map(Word::from)
+            .collect()
+    }
+
+    /// Like `lookup_with`, but reports bad letters or filters instead of panicking
+    fn try_lookup_with(
+        &self,
+        word: &str,
+        filter: &str,
+        options: &LookupOptions,
+    ) -> Result<Vec<Word>> {
+        self.alphabet().try_to_set(word)?;
+        let filter = if filter.is_empty() {
+            Filter::Permissive
+        } else {
+            Filter::parse(filter)?
+        };
+
+        Ok(self
+            .lookup_iter_with(word, &filter, options)
+            .map(Word::from)
+            .collect())
+    }
+
+    /// Look up the best `k` words which can be made from `word` according to `ranking`, best first,
+    /// without ranking every word found
+    fn lookup_top_k(&self, word: &str, k: usize, ranking: Ranking) -> Vec<Word> {
+        top_k(self.lookup_iter(word), k, ranking)
+    }
+
+    /// Like `lookup_top_k`, but reports letters which can't be looked up instead of panicking
+    fn try_lookup_top_k(&self, word: &str, k: usize, ranking: Ranking) -> Result<Vec<Word>> {
+        self.alphabet().try_to_set(word)?;
+
+        Ok(self.lookup_top_k(word, k, ranking))
+    }
+
+    /// Look up words which can be made from `word`, where any `?` or `*` is a blank standing in for
+    /// any one letter, along with the letters which the blanks stood for in each word
+    fn lookup_blanks(&self, word: &str) -> Vec<(Word, AlphaMultiset)> {
+        let letters = self.alphabet().to_set(word);
+
+        self.lookup_iter(word)
+            .map(|w| {
+                let filled = self.alphabet().to_set(&w).difference(&letters);
+                (w.to_word(), filled)
+            })
+            .collect()
+    }
+}
+
+/// A lookup index which words can be added to or removed from after it's built, like the words a
+/// game accepted or rejected
+///
+/// Edited words are normalized like the words of a wordlist, but aren't left out for their length,
+/// and any character other than letters of the alphabet or punctuation is rejected.
+pub trait EditableWordSearcher: WordSearcher {
+    /// Add `word` with `frequency`, or change its frequency if it's already in the index
+    fn insert(&mut self, word: &str, frequency: usize) {
+        self.try_insert(word, frequency)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `insert`, but reports characters which can't be part of a word instead of panicking
+    fn try_insert(&mut self, word: &str, frequency: usize) -> Result<()>;
+
+    /// Remove `word`, returning whether it was in the index
+    fn remove(&mut self, word: &str) -> bool;
+
+    /// Make the edits of `delta`, e.g. one saved when the index was last used
+    fn apply(&mut self, delta: &WordDelta) {
+        self.try_apply(delta).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `apply`, but reports added words which can't be part of the index instead of panicking
+    fn try_apply(&mut self, delta: &WordDelta) -> Result<()> {
+        for (word, frequency) in delta.iter() {
+            match frequency {
+                Some(frequency) => self.try_insert(word, frequency)?,
+                None => {
+                    self.remove(word);
+                }
+            }
         }
+
+        Ok(())
     }
 }
 
-fn embedded_wordlist_iter() -> impl Iterator<Item = String> {
-    use std::io::BufRead;
+/// What every word matching a filter and lookup options has in common, used to prune lookups early
+#[derive(Debug, Clone)]
+pub(crate) struct FilterBounds {
+    /// the fewest letters a matching word can have
+    pub min_len: usize,
+    /// the most letters a matching word can have
+    pub max_len: usize,
+    /// letters every matching word contains
+    pub required: AlphaMultiset,
+    /// letters no matching word contains
+    pub banned: AlphaMultiset,
+}
 
-    std::io::BufReader::new(EMBEDDED_WORDLIST)
-        .lines()
-        .map(Result::unwrap)
+impl Default for FilterBounds {
+    fn default() -> Self {
+        Self {
+            min_len: 0,
+            max_len: usize::MAX,
+            required: AlphaMultiset::new_empty(),
+            banned: AlphaMultiset::new_empty(),
+        }
+    }
 }
 
-fn path_to_iter<P: AsRef<std::path::Path>>(path: P) -> impl Iterator<Item = String> {
-    use std::io::BufRead;
+impl FilterBounds {
+    pub fn new(filter: &Filter, options: &LookupOptions, alphabet: &Alphabet) -> Self {
+        let (min_len, max_len) = filter.length_range();
+        let banned = options.banned_set(alphabet);
+        let bounds = Self {
+            min_len,
+            max_len: max_len.unwrap_or(usize::MAX),
+            required: AlphaMultiset::new_empty(),
+            banned,
+        };
+
+        let fixed = alphabet.try_chars_to_set(filter.fixed_letters());
+        let required = options.required_set(alphabet);
+
+        match (fixed, required) {
+            (Ok(fixed), Ok(required)) if fixed.is_disjoint(&bounds.banned) => Self {
+                required: fixed.simple_union(&required),
+                ..bounds
+            },
+            // letters outside of the alphabet, more repetitions of a letter than any looked up
+            // letters could hold, or banned letters fixed by the filter
+            _ => Self {
+                max_len: 0,
+                ..bounds
+            },
+        }
+    }
+
+    /// Whether any word made from `letters` could match
+    pub fn is_satisfiable(&self, letters: &AlphaMultiset) -> bool {
+        self.min_len <= self.max_len
+            && self.min_len <= letters.len()
+            && letters.has_subset(&self.required)
+            && self.required.is_disjoint(&self.banned)
+    }
 
-    let path = path.as_ref();
-    let f =
-        std::fs::File::open(path).unwrap_or_else(|_| panic!("Unable to find '{}'", path.display()));
+    /// Whether a word with exactly the letters of `set` could match
+    pub fn admits(&self, set: &AlphaMultiset) -> bool {
+        set.has_subset(&self.required) && set.is_disjoint(&self.banned)
+    }
 
-    std::io::BufReader::new(f).lines().map(Result::unwrap)
+    /// Whether a word containing all of `set`, and maybe more, could match
+    pub fn admits_superset(&self, set: &AlphaMultiset) -> bool {
+        set.is_disjoint(&self.banned)
+            && (self.max_len == usize::MAX
+                || set.simple_union(&self.required).len() <= self.max_len)
+    }
 }
 
-fn iter_to_wordmap<I>(words: I) -> HashMap<AlphaMultiset, Vec<Word>>
-where
-    I: Iterator,
-    I::Item: AsRef<str>,
-{
-    // remove non-letter characters and filter words < 3 characters long
-    let wordlist = words
-        .filter(|l| !l.as_ref().is_empty())
-        .map(|l| Word::from_freqlist_line(l.as_ref()))
-        .filter_map(|w| {
-            let s: String = w
-                .chars()
-                .filter(char::is_ascii_alphabetic)
-                .map(|c| c.to_ascii_lowercase())
-                .collect();
-
-            if s.len() >= 3 {
-                Some(Word::from_pair(s, w.frequency()))
-            } else {
-                None
-            }
-        });
+fn wordlist_to_wordmap(wordlist: Wordlist) -> (HashMap<AlphaMultiset, Vec<Word>>, Alphabet) {
+    try_wordlist_to_wordmap(wordlist).unwrap_or_else(|e| panic!("{}", e))
+}
 
+/// Group the words of `wordlist` by their letters, along with the alphabet of those letters
+fn try_wordlist_to_wordmap(
+    wordlist: Wordlist,
+) -> Result<(HashMap<AlphaMultiset, Vec<Word>>, Alphabet)> {
     let mut wordmap = HashMap::new();
+    let policy = *wordlist.normalization();
+    let alphabet = wordlist.alphabet().clone();
+
+    for w in wordlist.into_numbered_words()? {
+        let (line, w) = w?;
 
-    // construct a map from `AlphaMultiset` to strings which created such sets
-    // aka group anagrams and key them by some normal representation
-    for w in wordlist {
-        let w_norm = str_to_set(w.as_ref());
-        wordmap.entry(w_norm).or_insert_with(Vec::new).push(w);
+        let s = match policy
+            .normalize(&w, &alphabet)
+            .map_err(|e| e.at_line(line))?
+        {
+            Some(s) => s,
+            None => continue,
+        };
+
+        // construct a map from `AlphaMultiset` to strings which created such sets
+        // aka group anagrams and key them by some normal representation
+        let w_norm = alphabet.try_to_set(&s).map_err(|e| e.at_line(line))?;
+        wordmap
+            .entry(w_norm)
+            .or_insert_with(Vec::new)
+            .push(Word::from_pair(s, w.frequency()));
     }
 
-    return wordmap;
+    return Ok((wordmap, alphabet));
 }
 
-fn str_to_set(word: &str) -> AlphaMultiset {
-    std::iter::FromIterator::from_iter(
-        word.chars()
-            .filter(char::is_ascii_alphabetic)
-            .map(|c| c.to_ascii_lowercase()),
-    )
+/// Normalize a word added to or removed from an index after it's built into letters of `alphabet`,
+/// along with its set of letters, or `None` if it has no letters at all
+fn try_edited_word(word: &str, alphabet: &Alphabet) -> Result<Option<(String, AlphaMultiset)>> {
+    let policy = NormalizationPolicy {
+        min_len: 1,
+        max_len: usize::MAX,
+        punctuation: CharHandling::Strip,
+        other_chars: CharHandling::Reject,
+        fold_case: true,
+    };
+
+    match policy.normalize(word, alphabet)? {
+        Some(word) => {
+            let set = alphabet.try_to_set(&word)?;
+            Ok(Some((word, set)))
+        }
+        None => Ok(None),
+    }
 }
@@ -1,132 +1,191 @@
+use crate::{Alphabet, Error, Result};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::convert::TryInto;
 
-const NUM_BLOCKS: usize = 4;
+/// Blocks of lanes in each set, which columnar tables keep a column of each for
+pub(crate) const NUM_BLOCKS: usize = 5;
 const BLOCK_SIZE: usize = 64;
+const LANE_SIZE: usize = 8;
+const LANES_PER_BLOCK: usize = BLOCK_SIZE / LANE_SIZE;
+const NUM_LANES: usize = NUM_BLOCKS * LANES_PER_BLOCK;
+const NUM_LETTERS: usize = 26;
+/// Lane which counts blank tiles, which can stand in for any one letter
+const BLANK: usize = NUM_LANES - 1;
+
+/// Most letters an alphabet can have, as every lane but the blank one counts a letter
+pub const MAX_LETTERS: usize = BLANK;
 const A: usize = 'a' as usize;
 const Z: usize = 'z' as usize;
-const MAX_CHAR_REP: usize = NUM_BLOCKS * BLOCK_SIZE / 26;
 
+/// The top bit of every lane, which is kept clear so lanes can be compared without borrows
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Most occurrences of a single letter that can be stored in an `AlphaMultiset`
+pub const MAX_CHAR_REP: usize = (1 << (LANE_SIZE - 1)) - 1;
+
+/// 7-bit counters packed into 8-bit lanes, so that comparisons of all lanes can be done a block at a time
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Hash, Default, Serialize, Deserialize)]
-pub struct BitArray([u64; NUM_BLOCKS]);
+pub struct PackedCounts([u64; NUM_BLOCKS]);
 
-impl BitArray {
-    /// Create BitArray with all ones
-    pub fn new_ones() -> Self {
-        Self([u64::MAX, u64::MAX, u64::MAX, u64::MAX])
+impl PackedCounts {
+    /// Create PackedCounts with every lane at its maximum
+    pub fn new_full() -> Self {
+        Self([!HIGH_BITS; NUM_BLOCKS])
     }
 
-    /// Get specific bit, with no safety checks
-    pub unsafe fn get_unchecked(&self, i: usize) -> bool {
-        let b = i / BLOCK_SIZE;
-        let n = i % BLOCK_SIZE;
-        (self.0.get_unchecked(b) >> n) & 0b1 == 1
+    /// Get the count in a specific lane, with no safety checks
+    ///
+    /// # Safety
+    ///
+    /// `i` has to be less than the number of lanes, or this reads past the blocks.
+    pub unsafe fn get_unchecked(&self, i: usize) -> u8 {
+        let b = i / LANES_PER_BLOCK;
+        let n = (i % LANES_PER_BLOCK) * LANE_SIZE;
+        (self.0.get_unchecked(b) >> n) as u8
     }
 
-    /// Get specific bit
-    pub fn get(&self, i: usize) -> Option<bool> {
-        if i >= NUM_BLOCKS * BLOCK_SIZE {
+    /// Get the count in a specific lane
+    pub fn get(&self, i: usize) -> Option<u8> {
+        if i >= NUM_LANES {
             None
         } else {
             Some(unsafe { self.get_unchecked(i) })
         }
     }
 
-    /// Set specific bit
-    pub fn set(&mut self, i: usize, val: bool) {
-        if i >= NUM_BLOCKS * BLOCK_SIZE {
+    /// Set the count in a specific lane, ignoring counts which don't fit
+    pub fn set(&mut self, i: usize, val: u8) {
+        if i >= NUM_LANES || val as usize > MAX_CHAR_REP {
             return;
         }
 
-        let b = i / BLOCK_SIZE;
-        let n = i % BLOCK_SIZE;
+        let b = i / LANES_PER_BLOCK;
+        let n = (i % LANES_PER_BLOCK) * LANE_SIZE;
 
-        self.0[b] = (self.0[b] & !(1 << n)) | ((val as u64) << n);
+        self.0[b] = (self.0[b] & !(0xFF << n)) | ((val as u64) << n);
     }
 
+    /// Sum of all counts
     pub fn len(&self) -> u32 {
-        self.0[0].count_ones()
-            + self.0[1].count_ones()
-            + self.0[2].count_ones()
-            + self.0[3].count_ones()
+        self.0.iter().map(|&a| lane_sum(a)).sum()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0[0] == 0 && self.0[1] == 0 && self.0[2] == 0 && self.0[3] == 0
+        self.0.iter().all(|&a| a == 0)
     }
 
-    /// Check if `self` has `other` as a subset (not strict)
+    /// Check if every count of `self` is at least the corresponding count of `other`
     pub fn has_subset(&self, other: &Self) -> bool {
-        let bs = |a, b| a | b == a;
-
-        bs(self.0[0], other.0[0])
-            && bs(self.0[1], other.0[1])
-            && bs(self.0[2], other.0[2])
-            && bs(self.0[3], other.0[3])
+        self.blocks_with(other)
+            .all(|(a, b)| ge_lanes(a, b) == HIGH_BITS)
     }
 
-    /// Check if `self` is disjoint from `other`
+    /// Check if no lane is nonzero in both `self` and `other`
     pub fn is_disjoint(&self, other: &Self) -> bool {
-        let bs = |a, b| a & b == 0;
-
-        bs(self.0[0], other.0[0])
-            && bs(self.0[1], other.0[1])
-            && bs(self.0[2], other.0[2])
-            && bs(self.0[3], other.0[3])
+        self.blocks_with(other)
+            .all(|(a, b)| nonzero_lanes(a) & nonzero_lanes(b) == 0)
     }
 
-    /// Return the least index which is set
+    /// Return the least lane which is nonzero
     pub fn least_set_index(&self) -> usize {
-        let bs = |a: u64| a.trailing_zeros() as usize;
-
-        match (bs(self.0[0]), bs(self.0[1]), bs(self.0[2]), bs(self.0[3])) {
-            (BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE, a) => 3 * BLOCK_SIZE + a,
-            (BLOCK_SIZE, BLOCK_SIZE, a, _) => 2 * BLOCK_SIZE + a,
-            (BLOCK_SIZE, a, _, _) => BLOCK_SIZE + a,
-            (a, _, _, _) => a,
+        for (b, &block) in self.0.iter().enumerate() {
+            if block != 0 {
+                return b * LANES_PER_BLOCK + block.trailing_zeros() as usize / LANE_SIZE;
+            }
         }
+
+        NUM_LANES
     }
 
-    /// Return the greatest index which is set
-    pub fn greatest_set_index(&self) -> usize {
-        let bs = |a: u64| a.leading_zeros() as usize;
+    /// Return the lane-wise minimum
+    pub fn intersection(&self, other: &Self) -> PackedCounts {
+        self.map_blocks(other, |a, b| {
+            let mask = lane_mask(ge_lanes(a, b));
+            (b & mask) | (a & !mask)
+        })
+    }
 
-        match (bs(self.0[0]), bs(self.0[1]), bs(self.0[2]), bs(self.0[3])) {
-            (a, BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE) => 3 * BLOCK_SIZE + a,
-            (_, a, BLOCK_SIZE, BLOCK_SIZE) => 2 * BLOCK_SIZE + a,
-            (_, _, a, BLOCK_SIZE) => BLOCK_SIZE + a,
-            (_, _, _, a) => a,
-        }
+    /// Return the lane-wise maximum
+    pub fn simple_union(&self, other: &Self) -> PackedCounts {
+        self.map_blocks(other, |a, b| {
+            let mask = lane_mask(ge_lanes(a, b));
+            (a & mask) | (b & !mask)
+        })
+    }
+
+    /// Return the lane-wise saturating difference
+    pub fn difference(&self, other: &Self) -> PackedCounts {
+        self.map_blocks(other, |a, b| {
+            let ge = ge_lanes(a, b);
+            ((a | HIGH_BITS).wrapping_sub(b) & !HIGH_BITS) & lane_mask(ge)
+        })
     }
 
-    pub fn intersection(&self, other: &Self) -> BitArray {
-        BitArray([
-            self.0[0] & other.0[0],
-            self.0[1] & other.0[1],
-            self.0[2] & other.0[2],
-            self.0[3] & other.0[3],
-        ])
+    /// Pairs of corresponding blocks of `self` and `other`
+    #[inline(always)]
+    fn blocks_with<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (u64, u64)> + 'a {
+        self.0.iter().copied().zip(other.0.iter().copied())
     }
 
-    pub fn simple_union(&self, other: &Self) -> BitArray {
-        BitArray([
-            self.0[0] | other.0[0],
-            self.0[1] | other.0[1],
-            self.0[2] | other.0[2],
-            self.0[3] | other.0[3],
-        ])
+    /// Combine corresponding blocks of `self` and `other` with `f`
+    #[inline(always)]
+    fn map_blocks<F: Fn(u64, u64) -> u64>(&self, other: &Self, f: F) -> PackedCounts {
+        let mut blocks = [0; NUM_BLOCKS];
+        for (block, (a, b)) in blocks.iter_mut().zip(self.blocks_with(other)) {
+            *block = f(a, b);
+        }
+
+        PackedCounts(blocks)
     }
 }
 
-impl std::fmt::Binary for BitArray {
+/// Sum of the lanes of `a`
+#[inline(always)]
+fn lane_sum(a: u64) -> u32 {
+    // add neighbouring lanes into 16-bit lanes, which can then be summed by a multiplication without overflow
+    let pairs = (a & 0x00FF_00FF_00FF_00FF) + ((a >> 8) & 0x00FF_00FF_00FF_00FF);
+    (pairs.wrapping_mul(0x0001_0001_0001_0001) >> 48) as u32
+}
+
+/// Number of letters counted in block `need` which block `have` lacks, so the number of blanks
+/// needed to make up for them
+#[inline(always)]
+pub(crate) fn missing_letters(have: u64, need: u64) -> u32 {
+    let ge = ge_lanes(need, have);
+    lane_sum(((need | HIGH_BITS).wrapping_sub(have) & !HIGH_BITS) & lane_mask(ge))
+}
+
+/// Set the top bit of each lane where `a >= b`
+///
+/// Setting the top bit of every lane of `a` before subtracting means no lane can borrow from its
+/// neighbour, and the top bit survives exactly when the lane of `a` is at least the lane of `b`
+#[inline(always)]
+fn ge_lanes(a: u64, b: u64) -> u64 {
+    (a | HIGH_BITS).wrapping_sub(b) & HIGH_BITS
+}
+
+/// Set the top bit of each lane which is nonzero
+#[inline(always)]
+fn nonzero_lanes(a: u64) -> u64 {
+    ((a & !HIGH_BITS).wrapping_add(!HIGH_BITS) | a) & HIGH_BITS
+}
+
+/// Expand the top bit of each lane to the whole lane
+#[inline(always)]
+fn lane_mask(high_bits: u64) -> u64 {
+    (high_bits >> (LANE_SIZE - 1)).wrapping_mul(0xFF)
+}
+
+impl std::fmt::Binary for PackedCounts {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for i in 0..NUM_BLOCKS * BLOCK_SIZE {
+        for i in 0..NUM_LANES {
             unsafe {
                 write!(
                     f,
-                    "{}{}",
-                    if i > 0 && i % 8 == 0 { " " } else { "" },
-                    if self.get_unchecked(i) { 1 } else { 0 }
+                    "{}{:08b}",
+                    if i > 0 { " " } else { "" },
+                    self.get_unchecked(i)
                 )?
             }
         }
@@ -136,12 +195,85 @@ impl std::fmt::Binary for BitArray {
 }
 
 #[derive(Eq, PartialEq, Clone, Hash, Default, Serialize, Deserialize)]
-pub struct AlphaMultiset(BitArray);
+pub struct AlphaMultiset(PackedCounts);
 
 impl AlphaMultiset {
+    /// Construct a set from the number of occurrences of each letter of `alphabet`, in its order
+    pub fn try_from_counts(alpha_vec: &[u8], alphabet: &Alphabet) -> Result<Self> {
+        let max = MAX_LETTERS.min(alphabet.len());
+        let past_alphabet = alpha_vec.get(max..).unwrap_or(&[]);
+        if alpha_vec.len() > MAX_LETTERS || past_alphabet.iter().any(|&c| c > 0) {
+            return Err(Error::TooManyLetters {
+                count: alpha_vec.len(),
+                max,
+            });
+        }
+
+        let mut this = Self::default();
+
+        for (i, c) in alpha_vec.iter().enumerate() {
+            if *c > MAX_CHAR_REP as _ {
+                return Err(Error::TooManyRepetitions {
+                    ch: alphabet.letters()[i],
+                    count: *c as _,
+                    max: MAX_CHAR_REP,
+                });
+            }
+            this.0.set(i, *c);
+        }
+
+        Ok(this)
+    }
+
+    /// Construct a set from lowercase ASCII letters, where `?` or `*` are blanks
+    pub fn try_from_chars<I: IntoIterator<Item = char>>(chars: I) -> Result<Self> {
+        let mut long_vec = [0usize; NUM_LETTERS];
+        let mut blanks = 0;
+
+        for c in chars {
+            let cu = c as usize;
+            if c == '?' || c == '*' {
+                blanks += 1;
+                continue;
+            } else if !(A..=Z).contains(&cu) {
+                return Err(Error::InvalidChar(c));
+            }
+
+            long_vec[cu - A] += 1;
+        }
+
+        if blanks > MAX_CHAR_REP {
+            return Err(Error::TooManyRepetitions {
+                ch: '?',
+                count: blanks,
+                max: MAX_CHAR_REP,
+            });
+        }
+
+        let mut this = Self::default();
+        for (i, &c) in long_vec.iter().enumerate() {
+            if c > MAX_CHAR_REP {
+                return Err(Error::TooManyRepetitions {
+                    ch: (i as u8 + b'a') as char,
+                    count: c,
+                    max: MAX_CHAR_REP,
+                });
+            }
+            this.0.set(i, c as _);
+        }
+        this.0.set(BLANK, blanks as _);
+
+        Ok(this)
+    }
+
+    /// Construct a set from a string of lowercase ASCII letters
+    pub fn try_from_str(word: &str) -> Result<Self> {
+        Self::try_from_chars(word.chars())
+    }
+
     /// Return a set which will have any other set as a subset
     pub fn new_universal() -> Self {
-        Self(BitArray::new_ones())
+        Self(PackedCounts::new_full())
     }
 
     /// Return a set which will be a subset of any other set
@@ -158,9 +290,62 @@ impl AlphaMultiset {
         self.0.is_empty()
     }
 
-    /// Check if `self` has `other` as a subset (not strict)
+    /// Check if `self` has `other` as a subset (not strict),
+    /// where blanks in `self` can make up for any letters missing from `self`
     pub fn has_subset(&self, other: &Self) -> bool {
-        self.0.has_subset(&other.0)
+        self.0.has_subset(&other.0) || {
+            let blanks = self.blanks();
+            blanks > 0 && other.0.difference(&self.0).len() as usize <= blanks
+        }
+    }
+
+    /// Number of blank tiles
+    pub fn blanks(&self) -> usize {
+        self.0.get(BLANK).unwrap() as _
+    }
+
+    /// Return this set with all blanks removed
+    pub fn without_blanks(&self) -> Self {
+        let mut this = self.clone();
+        this.0.set(BLANK, 0);
+        this
+    }
+
+    /// Return this set with `blanks` blanks instead of the ones it has
+    pub(crate) fn with_blanks(&self, blanks: u8) -> Self {
+        let mut this = self.clone();
+        this.0.set(BLANK, blanks);
+        this
+    }
+
+    /// Bytes taken by a set written with `write_le_bytes`
+    pub(crate) const SET_BYTES: usize = NUM_BLOCKS * BLOCK_SIZE / 8;
+
+    /// Append the packed counts to `out`, a block at a time in little endian
+    pub(crate) fn write_le_bytes(&self, out: &mut Vec<u8>) {
+        for block in (self.0).0.iter() {
+            out.extend_from_slice(&block.to_le_bytes());
+        }
+    }
+
+    /// Read a set written with `write_le_bytes` from the first `SET_BYTES` of `bytes`
+    pub(crate) fn from_le_bytes(bytes: &[u8]) -> Self {
+        let mut blocks = [0; NUM_BLOCKS];
+        for (block, chunk) in blocks.iter_mut().zip(bytes.chunks_exact(8)) {
+            *block = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        Self::from_blocks(blocks)
+    }
+
+    /// The blocks of packed counts, for tables which keep each block in a column of its own
+    pub(crate) fn blocks(&self) -> &[u64; NUM_BLOCKS] {
+        &(self.0).0
+    }
+
+    /// Rebuild a set from the blocks returned by `blocks`
+    pub(crate) fn from_blocks(blocks: [u64; NUM_BLOCKS]) -> Self {
+        Self(PackedCounts(blocks))
     }
 
     /// Check if `self` is disjoint from `other`
@@ -168,24 +353,42 @@ impl AlphaMultiset {
         self.0.is_disjoint(&other.0)
     }
 
-    /// Get the least alpha value which is in the set, in lowercase
-    pub fn least_entry(&self) -> u8 {
-        (self.0.least_set_index() / MAX_CHAR_REP) as u8 + 'a' as u8
+    /// Number of occurrences of a lowercase ASCII letter, in a set of English letters like the
+    /// ones `try_from_chars` makes, or `None` for any other byte
+    pub fn count(&self, ch: u8) -> Option<usize> {
+        if !ch.is_ascii_lowercase() {
+            return None;
+        }
+
+        self.0.get((ch - b'a') as usize).map(|c| c as _)
+    }
+
+    /// The least lowercase ASCII letter in a set of English letters, or `None` if it has no letters
+    /// besides blanks
+    pub fn least_entry(&self) -> Option<u8> {
+        let i = self.without_blanks().0.least_set_index();
+        if i < NUM_LETTERS {
+            Some(i as u8 + b'a')
+        } else {
+            None
+        }
     }
 
     /// Remove a single entry from this multiset
     /// Returns true if an entry was removed, false otherwise
     pub fn remove_entry(&mut self, ch: u8) -> bool {
-        let ci = (ch as usize - A) * MAX_CHAR_REP;
+        if !ch.is_ascii_lowercase() {
+            return false;
+        }
+        let ci = (ch - b'a') as usize;
 
-        // could this be faster?
-        for i in (0..MAX_CHAR_REP).rev() {
-            if self.0.get(ci + i).unwrap() {
-                self.0.set(ci + i, false);
-                return true;
+        match self.0.get(ci) {
+            Some(c) if c > 0 => {
+                self.0.set(ci, c - 1);
+                true
             }
+            _ => false,
         }
-        return false;
     }
 
     pub fn intersection(&self, other: &Self) -> Self {
@@ -198,39 +401,15 @@ impl AlphaMultiset {
 
     /// Compute the multiset of `self - other`
     pub fn difference(&self, other: &Self) -> Self {
-        let mut dif = Self::default();
-
-        for a in 0..26 {
-            let mut di = 0;
-            for i in (0..MAX_CHAR_REP).rev() {
-                match (
-                    self.0.get(a * MAX_CHAR_REP + i),
-                    other.0.get(a * MAX_CHAR_REP + i),
-                ) {
-                    (Some(true), Some(false)) => {
-                        dif.0.set(a * MAX_CHAR_REP + di, true);
-                        di += 1;
-                    }
-                    (Some(true), Some(true)) | (Some(false), Some(true)) => break,
-                    _ => {}
-                }
-            }
-        }
-
-        return dif;
+        Self(self.0.difference(&other.0))
     }
 
-    pub fn char_counts(&self) -> [u8; 26] {
-        let mut counts: [u8; 26] = Default::default();
+    /// Number of occurrences of each letter, in the order of the alphabet
+    pub fn char_counts(&self) -> [u8; MAX_LETTERS] {
+        let mut counts = [0; MAX_LETTERS];
 
-        for i in 0..26 {
-            for b in 0..MAX_CHAR_REP {
-                if self.0.get(i * MAX_CHAR_REP + b).unwrap() {
-                    counts[i] += 1;
-                } else {
-                    break;
-                }
-            }
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count = self.0.get(i).unwrap();
         }
 
         return counts;
@@ -251,69 +430,39 @@ impl PartialOrd for AlphaMultiset {
     }
 }
 
-impl AsRef<BitArray> for AlphaMultiset {
-    fn as_ref(&self) -> &BitArray {
+impl AsRef<PackedCounts> for AlphaMultiset {
+    fn as_ref(&self) -> &PackedCounts {
         &self.0
     }
 }
 
-impl AsMut<BitArray> for AlphaMultiset {
-    fn as_mut(&mut self) -> &mut BitArray {
+impl AsMut<PackedCounts> for AlphaMultiset {
+    fn as_mut(&mut self) -> &mut PackedCounts {
         &mut self.0
     }
 }
 
-impl std::convert::Into<BitArray> for AlphaMultiset {
-    fn into(self) -> BitArray {
-        self.0
+impl std::convert::From<AlphaMultiset> for PackedCounts {
+    fn from(set: AlphaMultiset) -> Self {
+        set.0
     }
 }
 
 impl std::convert::From<&[u8; 26]> for AlphaMultiset {
     fn from(alpha_vec: &[u8; 26]) -> Self {
-        let mut this = Self::default();
-
-        for (i, c) in alpha_vec.iter().enumerate() {
-            if *c > MAX_CHAR_REP as _ {
-                panic!(
-                    "Can only handle {} repetitions of chars, but '{}' occurs {} times",
-                    MAX_CHAR_REP,
-                    ((i as u8) + ('a' as u8)) as char,
-                    c
-                );
-            }
-            for v in 0..*c {
-                this.0.set(i * MAX_CHAR_REP + (v as usize), true);
-            }
-        }
-
-        this
+        Self::try_from_counts(alpha_vec, &Alphabet::english()).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl<'a> std::convert::From<&'a str> for AlphaMultiset {
     fn from(word: &'a str) -> Self {
-        std::iter::FromIterator::from_iter(word.chars())
+        Self::try_from_str(word).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl std::iter::FromIterator<char> for AlphaMultiset {
     fn from_iter<I: IntoIterator<Item = char>>(words: I) -> Self {
-        let mut long_vec = <[u8; 26]>::default();
-
-        for c in words {
-            let cu = c as usize;
-            if cu < A || cu > Z {
-                panic!(
-                    "Can only handle lowercase ASCII alpha chars, but got '{}'",
-                    c
-                );
-            }
-
-            long_vec[cu - A] += 1;
-        }
-
-        From::from(&long_vec)
+        Self::try_from_chars(words).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -323,17 +472,22 @@ impl std::fmt::Binary for AlphaMultiset {
     }
 }
 
+/// Letters are shown as English ones, with any lanes past `z` shown by their index like `[26]`,
+/// see `Alphabet::set_to_string` to show the letters of other alphabets
 impl std::fmt::Debug for AlphaMultiset {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for i in 0..26 {
-            for b in 0..MAX_CHAR_REP {
-                if self.0.get(i * MAX_CHAR_REP + b).unwrap() {
+        for i in 0..MAX_LETTERS {
+            for _ in 0..self.0.get(i).unwrap() {
+                if i < NUM_LETTERS {
                     write!(f, "{}", (A + i) as u8 as char)?;
                 } else {
-                    break;
+                    write!(f, "[{}]", i)?;
                 }
             }
         }
+        for _ in 0..self.blanks() {
+            write!(f, "?")?;
+        }
         Ok(())
     }
 }
@@ -359,15 +513,63 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Can only handle 9 repetitions of chars, but 'a' occurs 12 times")]
+    #[should_panic(expected = "Can only handle 127 repetitions of chars, but 'a' occurs 130 times")]
     fn test_max_char_reps() {
-        AlphaMultiset::from("aaabaaabaaabaaa");
+        let _ = AlphaMultiset::from(&*"a".repeat(130));
+    }
+
+    #[test]
+    fn test_counts_in_alphabet() {
+        let spanish = Alphabet::spanish();
+        let mut counts = [0; MAX_LETTERS];
+        counts[spanish.index('ñ').unwrap()] = 130;
+        assert!(matches!(
+            AlphaMultiset::try_from_counts(&counts, &spanish),
+            Err(Error::TooManyRepetitions { ch: 'ñ', .. })
+        ));
+
+        // a count of a lane past the letters of the alphabet
+        counts = [0; MAX_LETTERS];
+        counts[spanish.len()] = 1;
+        assert!(matches!(
+            AlphaMultiset::try_from_counts(&counts, &spanish),
+            Err(Error::TooManyLetters { .. })
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "Can only handle lowercase ASCII alpha chars, but got '-'")]
+    fn test_long_rack() {
+        let rack = AlphaMultiset::from("eeeeeeeeeeeeaaaaaaaaaiiiiiiiiioooooooonnnnnnrrrrrr");
+        assert_eq!(rack.len(), 50);
+        assert_eq!(rack.count(b'e'), Some(12));
+        assert_eq!(rack.count(b'z'), Some(0));
+        assert_eq!(rack.count(b'E'), None);
+        assert_eq!(rack.count(b'-'), None);
+        assert!(rack.has_subset(&AlphaMultiset::from("eeeeeeeeeeee")));
+        assert!(!rack.has_subset(&AlphaMultiset::from("eeeeeeeeeeeee")));
+    }
+
+    #[test]
+    fn test_max_count_subset() {
+        let full = AlphaMultiset::from(&*"z".repeat(MAX_CHAR_REP));
+        let almost = AlphaMultiset::from(&*"z".repeat(MAX_CHAR_REP - 1));
+        assert!(full.has_subset(&almost));
+        assert!(!almost.has_subset(&full));
+        assert!(AlphaMultiset::new_universal().has_subset(&full));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected character '-'")]
     fn test_only_ascii() {
-        AlphaMultiset::from("-");
+        let _ = AlphaMultiset::from("-");
+    }
+
+    #[test]
+    fn test_try_from_reports_bad_char() {
+        assert!(matches!(
+            AlphaMultiset::try_from_str("ab-c"),
+            Err(Error::InvalidChar('-'))
+        ));
     }
 
     #[test]
@@ -392,27 +594,30 @@ mod tests {
 
     #[test]
     fn test_least_entry() {
-        assert_eq!(AlphaMultiset::from("maaz").least_entry(), b'a');
-        assert_ne!(AlphaMultiset::from("mz").least_entry(), b'z');
+        assert_eq!(AlphaMultiset::from("maaz").least_entry(), Some(b'a'));
+        assert_ne!(AlphaMultiset::from("mz").least_entry(), Some(b'z'));
+        assert_eq!(AlphaMultiset::from("z??").least_entry(), Some(b'z'));
+        assert_eq!(AlphaMultiset::from("??").least_entry(), None);
+        assert_eq!(AlphaMultiset::from("").least_entry(), None);
     }
 
     #[test]
     fn test_remove_least() {
         let mut set = AlphaMultiset::from("maazm");
         assert_eq!(set.to_string(), "aammz");
-        assert_eq!(set.least_entry(), b'a');
+        assert_eq!(set.least_entry(), Some(b'a'));
 
-        set.remove_entry(set.least_entry());
+        set.remove_entry(set.least_entry().unwrap());
         assert_eq!(set.to_string(), "ammz");
-        assert_eq!(set.least_entry(), b'a');
+        assert_eq!(set.least_entry(), Some(b'a'));
 
-        set.remove_entry(set.least_entry());
+        set.remove_entry(set.least_entry().unwrap());
         assert_eq!(set.to_string(), "mmz");
-        assert_eq!(set.least_entry(), b'm');
+        assert_eq!(set.least_entry(), Some(b'm'));
 
-        set.remove_entry(set.least_entry());
+        set.remove_entry(set.least_entry().unwrap());
         assert_eq!(set.to_string(), "mz");
-        assert_eq!(set.least_entry(), b'm');
+        assert_eq!(set.least_entry(), Some(b'm'));
     }
 
     #[test]
@@ -422,4 +627,26 @@ mod tests {
             AlphaMultiset::from("a")
         );
     }
+
+    #[test]
+    fn test_blanks() {
+        let rack = AlphaMultiset::from("a?b*");
+        assert_eq!(rack.blanks(), 2);
+        assert_eq!(rack.to_string(), "ab??");
+        assert_eq!(rack.without_blanks(), AlphaMultiset::from("ab"));
+        assert!(rack.has_subset(&AlphaMultiset::from("abzz")));
+        assert!(rack.has_subset(&AlphaMultiset::from("aab")));
+        assert!(!rack.has_subset(&AlphaMultiset::from("abzzz")));
+        assert!(!rack.has_subset(&AlphaMultiset::from("xyz")));
+    }
+
+    #[test]
+    fn test_intersection_union() {
+        let a = AlphaMultiset::from("aaabcc");
+        let b = AlphaMultiset::from("abbbd");
+        assert_eq!(a.intersection(&b), AlphaMultiset::from("ab"));
+        assert_eq!(a.simple_union(&b), AlphaMultiset::from("aaabbbccd"));
+        assert!(!a.is_disjoint(&b));
+        assert!(a.is_disjoint(&AlphaMultiset::from("xyz")));
+    }
 }
@@ -1,14 +1,38 @@
+use super::set_automaton::{SetAutomaton, SetAutomatonIter};
 use super::{
-    embedded_wordlist_iter, iter_to_wordmap, path_to_iter, str_to_set, AlphaMultiset, WordSearcher,
+    try_wordlist_to_wordmap, wordlist_to_wordmap, AlphaMultiset, FilterBounds, WordSearcher,
 };
-use crate::Word;
-use regex::bytes::{RegexSet, RegexSetBuilder};
+use crate::{Alphabet, Filter, LookupOptions, LookupStats, Result, Word, WordRef, Wordlist};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+/// A minimal acyclic automaton accepting the letters of every set in order, like `aabce`
+///
+/// Each state counts the sequences accepted from it, so the words of a sequence are found by its
+/// rank among all the sequences, without storing anything in the final states.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutomatonSearcher {
-    rset: RegexSet,
+    states: Vec<State>,
+    transitions: Vec<Transition>,
+    /// words of every set, in the order of their sequences
     words: Vec<Vec<Word>>,
+    alphabet: Alphabet,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct State {
+    is_final: bool,
+    /// index of the first transition out of the state, in order of the letters
+    first: u32,
+    len: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Transition {
+    letter: u8,
+    target: u32,
+    /// number of sequences accepted from the state which come before those through this transition
+    rank: u32,
 }
 
 impl Default for AutomatonSearcher {
@@ -18,41 +42,225 @@ impl Default for AutomatonSearcher {
 }
 
 impl AutomatonSearcher {
-    pub fn from_wordlist<P: AsRef<std::path::Path>>(path: P) -> Self {
-        let (rset, words) = build_regex_set(iter_to_wordmap(path_to_iter(path)));
-        Self { rset, words }
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist.into())?;
+        Ok(Self::from_wordmap(wordmap, alphabet))
     }
     pub fn from_embedded_wordlist() -> Self {
-        let (rset, words) = build_regex_set(iter_to_wordmap(embedded_wordlist_iter()));
-        Self { rset, words }
+        let (wordmap, alphabet) = wordlist_to_wordmap(Wordlist::embedded());
+        Self::from_wordmap(wordmap, alphabet)
+    }
+
+    fn from_wordmap(wordmap: HashMap<AlphaMultiset, Vec<Word>>, alphabet: Alphabet) -> Self {
+        let mut sets: Vec<_> = wordmap
+            .into_iter()
+            .map(|(set, words)| (letter_sequence(&set), words))
+            .collect();
+        sets.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut builder = AutomatonBuilder::default();
+        for (sequence, _) in sets.iter() {
+            builder.add(sequence);
+        }
+        let (states, transitions) = builder.finish();
+
+        Self {
+            states,
+            transitions,
+            words: sets.into_iter().map(|(_, words)| words).collect(),
+            alphabet,
+        }
+    }
+
+    fn lookup_iter_bounded(&self, word: &str, prune: FilterBounds) -> SetAutomatonIter<'_, Self> {
+        SetAutomatonIter::new(self, &self.alphabet.to_set(word), prune)
+    }
+}
+
+impl SetAutomaton for AutomatonSearcher {
+    /// a state, and the rank of the sequence read on the way to it
+    type State = (u32, u32);
+
+    fn root(&self) -> Self::State {
+        // the root is the last state to be added
+        (self.states.len() as u32 - 1, 0)
+    }
+
+    fn visit(
+        &self,
+        (state, rank): Self::State,
+        transitions: &mut Vec<(usize, Self::State)>,
+    ) -> Option<&[Word]> {
+        let state = self.states[state as usize];
+        let start = state.first as usize;
+
+        transitions.extend(
+            self.transitions[start..start + state.len as usize]
+                .iter()
+                .map(|t| (t.letter as usize, (t.target, rank + t.rank))),
+        );
+
+        if state.is_final {
+            Some(&self.words[rank as usize])
+        } else {
+            None
+        }
     }
 }
 
 impl WordSearcher for AutomatonSearcher {
-    fn lookup(&self, word: &str) -> Vec<Word> {
-        let norm_word = str_to_set(word).to_string();
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        Box::new(self.lookup_iter_bounded(word, FilterBounds::default()))
+    }
 
-        self.rset
-            .matches(norm_word.as_bytes())
-            .into_iter()
-            .flat_map(|i| self.words[i].clone())
-            .collect()
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the states visited
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut iter = self.lookup_iter_bounded(word, FilterBounds::default());
+            let words = iter.by_ref().map(Word::from).collect();
+            (words, iter.steps)
+        })
+    }
+
+    /// Doesn't follow transitions which are too long, use banned letters or skip required ones
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        Box::new(
+            self.lookup_iter_bounded(word, bounds)
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+}
+
+/// The letters of `set` in order, each repeated as many times as the set has it
+fn letter_sequence(set: &AlphaMultiset) -> Vec<u8> {
+    let mut sequence = Vec::with_capacity(set.len());
+    for (letter, &count) in set.char_counts().iter().enumerate() {
+        sequence.extend(std::iter::repeat(letter as u8).take(count as usize));
     }
+
+    return sequence;
+}
+
+/// Builds a minimal automaton from sequences added in order, by merging the states of each
+/// sequence with equivalent ones as soon as no later sequence can add to them
+#[derive(Default)]
+struct AutomatonBuilder {
+    states: Vec<State>,
+    transitions: Vec<Transition>,
+    /// sequences accepted from each state
+    accepted: Vec<u32>,
+    /// the state with the same finality and transitions as every state added so far
+    register: HashMap<(bool, Vec<Transition>), u32>,
+    /// states along the last sequence added, which may still gain transitions: whether each is
+    /// final and its transitions so far, the last of which leads to the next one
+    unchecked: Vec<(bool, Vec<Transition>)>,
+    last: Vec<u8>,
 }
 
-fn build_regex_set(wordmap: HashMap<AlphaMultiset, Vec<Word>>) -> (RegexSet, Vec<Vec<Word>>) {
-    let (sets, words): (Vec<_>, Vec<_>) = wordmap.into_iter().unzip();
+impl AutomatonBuilder {
+    fn add(&mut self, sequence: &[u8]) {
+        if self.unchecked.is_empty() {
+            self.unchecked.push((false, Vec::new()));
+        }
+
+        let prefix = sequence
+            .iter()
+            .zip(self.last.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.freeze_to(prefix);
+
+        for &letter in &sequence[prefix..] {
+            let transitions = &mut self.unchecked.last_mut().unwrap().1;
+            transitions.push(Transition {
+                letter,
+                target: 0,
+                rank: 0,
+            });
+            self.unchecked.push((false, Vec::new()));
+        }
+        self.unchecked.last_mut().unwrap().0 = true;
+        self.last = sequence.to_vec();
+    }
+
+    /// Merge or add the unchecked states past the first `len` letters of the last sequence
+    fn freeze_to(&mut self, len: usize) {
+        while self.unchecked.len() > len + 1 {
+            let state = self.unchecked.pop().unwrap();
+            let target = self.freeze(state);
+            let (_, transitions) = self.unchecked.last_mut().unwrap();
+            transitions.last_mut().unwrap().target = target;
+        }
+    }
 
-    let rset = RegexSetBuilder::new(sets.into_iter().map(|s| s.to_string()).map(|mut s| {
-        for i in (0..=s.len()).rev() {
-            s.insert_str(i, r"[a-z]*");
+    fn freeze(&mut self, (is_final, mut transitions): (bool, Vec<Transition>)) -> u32 {
+        let mut accepted = is_final as u32;
+        for transition in transitions.iter_mut() {
+            transition.rank = accepted;
+            accepted += self.accepted[transition.target as usize];
         }
-        return s;
-    }))
-    .size_limit(2 << 28)
-    .unicode(false)
-    .build()
-    .expect("failed to build RegexSet");
 
-    return (rset, words);
+        let key = (is_final, transitions);
+        if let Some(&state) = self.register.get(&key) {
+            return state;
+        }
+
+        self.states.push(State {
+            is_final,
+            first: self.transitions.len() as u32,
+            len: key.1.len() as u32,
+        });
+        self.transitions.extend(key.1.iter());
+        self.accepted.push(accepted);
+
+        let state = self.states.len() as u32 - 1;
+        self.register.insert(key, state);
+        return state;
+    }
+
+    /// The states and transitions of the automaton, with the root last
+    fn finish(mut self) -> (Vec<State>, Vec<Transition>) {
+        if self.unchecked.is_empty() {
+            self.unchecked.push((false, Vec::new()));
+        }
+        self.freeze_to(0);
+
+        // no other state accepts the same sequences as the root, which accepts the longest ones, so
+        // it's always added last
+        let root = self.unchecked.pop().unwrap();
+        self.freeze(root);
+
+        return (self.states, self.transitions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimal_automaton() {
+        let bytes = b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\ndab 1\n";
+        let automaton = AutomatonSearcher::from_wordlist(Wordlist::from_bytes(bytes));
+
+        // the sets `abd`, `abde`, `acdef`, `ace`, `acef` and `adef` need 14 states in a trie, but
+        // states accepting the same rest are merged: `acd` and `ad` both only accept `ef`, `acde`
+        // and `ade` both only `f`, and every final state without transitions is the same one
+        assert_eq!(automaton.states.len(), 9);
+    }
 }
@@ -0,0 +1,255 @@
+use super::alpha_multiset::NUM_BLOCKS;
+use super::{
+    try_wordlist_to_wordmap, wordlist_to_wordmap, AlphaMultiset, FilterBounds, WordSearcher,
+};
+use crate::{Alphabet, Filter, LookupOptions, LookupStats, Result, Word, WordRef, Wordlist};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+mod kernel;
+
+/// A linear scan like `SimpleSearcher`, over a table which keeps each block of the sets' packed
+/// counts in a column of its own, so many sets can be checked at once with SIMD instructions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnarSearcher {
+    /// block `b` of every set in column `b`, longest sets first
+    columns: [Vec<u64>; NUM_BLOCKS],
+    /// words of every set, in the order of the columns
+    words: Vec<Vec<Word>>,
+    /// index of the first set of at most `l` letters at index `l`, up to the longest set
+    length_starts: Vec<usize>,
+    alphabet: Alphabet,
+}
+
+impl Default for ColumnarSearcher {
+    fn default() -> Self {
+        Self::from_embedded_wordlist()
+    }
+}
+
+impl ColumnarSearcher {
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist.into())?;
+        Ok(Self::from_wordmap(wordmap, alphabet))
+    }
+
+    pub fn from_embedded_wordlist() -> Self {
+        let (wordmap, alphabet) = wordlist_to_wordmap(Wordlist::embedded());
+        Self::from_wordmap(wordmap, alphabet)
+    }
+
+    fn from_wordmap(map: HashMap<AlphaMultiset, Vec<Word>>, alphabet: Alphabet) -> Self {
+        let mut sets: Vec<_> = map.into_iter().collect();
+        // ties are broken by the counts themselves, so lookups always find words in the same order
+        sets.sort_unstable_by(|(a, _), (b, _)| {
+            b.len()
+                .cmp(&a.len())
+                .then_with(|| a.as_ref().cmp(b.as_ref()))
+        });
+
+        let longest = sets.first().map_or(0, |(set, _)| set.len());
+        let length_starts = (0..=longest)
+            .map(|l| {
+                sets.iter()
+                    .position(|(set, _)| set.len() <= l)
+                    .unwrap_or(sets.len())
+            })
+            .collect();
+
+        let mut columns: [Vec<u64>; NUM_BLOCKS] = Default::default();
+        let mut words = Vec::with_capacity(sets.len());
+        for (set, set_words) in sets {
+            for (column, &block) in columns.iter_mut().zip(set.blocks().iter()) {
+                column.push(block);
+            }
+            words.push(set_words);
+        }
+
+        Self {
+            columns,
+            words,
+            length_starts,
+            alphabet,
+        }
+    }
+
+    /// The range of the table holding sets of `min` up to `max` letters
+    fn length_range(&self, min: usize, max: usize) -> std::ops::Range<usize> {
+        let first_at_most = |l: usize| self.length_starts[l.min(self.length_starts.len() - 1)];
+        let start = first_at_most(max);
+        let end = match min.checked_sub(1) {
+            Some(shorter) => first_at_most(shorter),
+            None => self.words.len(),
+        };
+
+        start..end.max(start)
+    }
+
+    fn lookup_iter_bounded(
+        &self,
+        word: &str,
+        prune: Option<FilterBounds>,
+    ) -> ColumnarLookupIter<'_> {
+        let letter_set = self.alphabet.to_set(word);
+        let range = match &prune {
+            Some(prune) if !prune.is_satisfiable(&letter_set) => 0..0,
+            Some(prune) => self.length_range(prune.min_len, prune.max_len.min(letter_set.len())),
+            None => self.length_range(0, letter_set.len()),
+        };
+
+        ColumnarLookupIter {
+            searcher: self,
+            kernel: kernel::select(),
+            letters: *letter_set.blocks(),
+            blanks: letter_set.blanks() as u32,
+            prune,
+            next: range.start,
+            end: range.end,
+            chunk_start: range.start,
+            fitting: 0,
+            words: [].iter(),
+            steps: 0,
+        }
+    }
+}
+
+impl WordSearcher for ColumnarSearcher {
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        Box::new(self.lookup_iter_bounded(word, None))
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the sets checked
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut iter = self.lookup_iter_bounded(word, None);
+            let words = iter.by_ref().map(Word::from).collect();
+            (words, iter.steps)
+        })
+    }
+
+    /// Only scans the words of the lengths the filter allows, skipping sets with the wrong letters
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        Box::new(
+            self.lookup_iter_bounded(word, Some(bounds))
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+}
+
+/// Scan through the table a chunk of sets at a time
+struct ColumnarLookupIter<'a> {
+    searcher: &'a ColumnarSearcher,
+    /// the SIMD kernel checking each chunk, selected when the lookup starts
+    kernel: kernel::Kernel,
+    letters: [u64; NUM_BLOCKS],
+    blanks: u32,
+    /// letters which sets must or must not have, if any
+    prune: Option<FilterBounds>,
+    /// the first set of the next chunk, and the end of the sets to scan
+    next: usize,
+    end: usize,
+    /// the first set of the last chunk scanned, and a bit for each set of it which fits
+    chunk_start: usize,
+    fitting: u64,
+    /// words of the last set found, which are yet to be returned
+    words: std::slice::Iter<'a, Word>,
+    /// sets checked so far
+    steps: usize,
+}
+
+impl<'a> Iterator for ColumnarLookupIter<'a> {
+    type Item = WordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.words.next() {
+                return Some(word.into());
+            }
+
+            if self.fitting != 0 {
+                let i = self.chunk_start + self.fitting.trailing_zeros() as usize;
+                self.fitting &= self.fitting - 1;
+
+                if let Some(prune) = &self.prune {
+                    let columns = &self.searcher.columns;
+                    let mut blocks = [0; NUM_BLOCKS];
+                    for (block, column) in blocks.iter_mut().zip(columns.iter()) {
+                        *block = column[i];
+                    }
+                    if !prune.admits(&AlphaMultiset::from_blocks(blocks)) {
+                        continue;
+                    }
+                }
+
+                self.words = self.searcher.words[i].iter();
+                continue;
+            }
+
+            if self.next >= self.end {
+                return None;
+            }
+            let chunk_end = self.end.min(self.next + kernel::CHUNK_SIZE);
+            self.chunk_start = self.next;
+            self.fitting = (self.kernel)(
+                &self.searcher.columns,
+                self.next,
+                chunk_end,
+                &self.letters,
+                self.blanks,
+            );
+            self.steps += chunk_end - self.next;
+            self.next = chunk_end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel_matches_portable() {
+        let searcher = ColumnarSearcher::from_wordlist(Wordlist::from_bytes(
+            b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\n",
+        ));
+        let end = searcher.words.len();
+        let kernel = kernel::select();
+
+        for letters in &["facedb", "ab?", "??", "", "zzz", "eeeeecafd"] {
+            let set = Alphabet::english().to_set(letters);
+            let blanks = set.blanks() as u32;
+
+            // every start and end, so the SIMD kernels have to handle every remainder
+            for start in 0..end {
+                for chunk_end in start..=end {
+                    assert_eq!(
+                        kernel(&searcher.columns, start, chunk_end, set.blocks(), blanks),
+                        kernel::fitting_sets_portable(
+                            &searcher.columns,
+                            start,
+                            chunk_end,
+                            set.blocks(),
+                            blanks
+                        )
+                    );
+                }
+            }
+        }
+    }
+}
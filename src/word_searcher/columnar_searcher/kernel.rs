@@ -0,0 +1,216 @@
+//! Subset checks of many sets of a columnar table at once
+//!
+//! A set of the table fits the looked up letters if the letters it has beyond them, summed over
+//! every block, are no more than the looked up blanks. SIMD kernels compute those sums for several
+//! sets at once with saturating byte subtractions, so blanks cost nothing extra.
+
+use super::super::alpha_multiset::{missing_letters, NUM_BLOCKS};
+
+/// Most sets checked by one call to `fitting_sets`, one per bit of its result
+pub(super) const CHUNK_SIZE: usize = 64;
+
+/// Set bit `i` of the result if set `start + i` of `columns` fits into `letters` with `blanks`
+/// blanks, for the sets from `start` up to `end`, which are at most `CHUNK_SIZE` apart
+pub(super) type Kernel = fn(&[Vec<u64>; NUM_BLOCKS], usize, usize, &[u64; NUM_BLOCKS], u32) -> u64;
+
+/// The fastest kernel the CPU running this supports, which is worth detecting once per lookup
+/// rather than once per chunk
+pub(super) fn select() -> Kernel {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            fitting_sets_avx2_detected
+        } else {
+            fitting_sets_sse2_baseline
+        }
+    }
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        fitting_sets_simd128
+    }
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    {
+        fitting_sets_portable
+    }
+}
+
+/// `fitting_sets_avx2`, only ever selected once AVX2 is detected
+#[cfg(target_arch = "x86_64")]
+fn fitting_sets_avx2_detected(
+    columns: &[Vec<u64>; NUM_BLOCKS],
+    start: usize,
+    end: usize,
+    letters: &[u64; NUM_BLOCKS],
+    blanks: u32,
+) -> u64 {
+    debug_assert!(end - start <= CHUNK_SIZE);
+    unsafe { fitting_sets_avx2(columns, start, end, letters, blanks) }
+}
+
+/// `fitting_sets_sse2`, which every x86_64 CPU supports
+#[cfg(target_arch = "x86_64")]
+fn fitting_sets_sse2_baseline(
+    columns: &[Vec<u64>; NUM_BLOCKS],
+    start: usize,
+    end: usize,
+    letters: &[u64; NUM_BLOCKS],
+    blanks: u32,
+) -> u64 {
+    debug_assert!(end - start <= CHUNK_SIZE);
+    unsafe { fitting_sets_sse2(columns, start, end, letters, blanks) }
+}
+
+/// One set at a time, a block of lanes at a time
+pub(super) fn fitting_sets_portable(
+    columns: &[Vec<u64>; NUM_BLOCKS],
+    start: usize,
+    end: usize,
+    letters: &[u64; NUM_BLOCKS],
+    blanks: u32,
+) -> u64 {
+    let mut fitting = 0;
+
+    for i in start..end {
+        let missing: u32 = (0..NUM_BLOCKS)
+            .map(|b| missing_letters(letters[b], columns[b][i]))
+            .sum();
+        if missing <= blanks {
+            fitting |= 1 << (i - start);
+        }
+    }
+
+    return fitting;
+}
+
+/// Four sets at a time
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn fitting_sets_avx2(
+    columns: &[Vec<u64>; NUM_BLOCKS],
+    start: usize,
+    end: usize,
+    letters: &[u64; NUM_BLOCKS],
+    blanks: u32,
+) -> u64 {
+    use std::arch::x86_64::*;
+
+    let zero = _mm256_setzero_si256();
+    let max_missing = _mm256_set1_epi64x(blanks as i64);
+    let mut have = [zero; NUM_BLOCKS];
+    for (have, &letters) in have.iter_mut().zip(letters.iter()) {
+        *have = _mm256_set1_epi64x(letters as i64);
+    }
+
+    let mut fitting = 0;
+    let mut i = start;
+    while i + 4 <= end {
+        let mut missing = zero;
+        for b in 0..NUM_BLOCKS {
+            let need = _mm256_loadu_si256(columns[b].as_ptr().add(i) as *const __m256i);
+            // sums the letters missing from each lane into the 64 bits of each set
+            let lanes = _mm256_subs_epu8(need, have[b]);
+            missing = _mm256_add_epi64(missing, _mm256_sad_epu8(lanes, zero));
+        }
+
+        let too_many = _mm256_cmpgt_epi64(missing, max_missing);
+        let fits = !_mm256_movemask_pd(_mm256_castsi256_pd(too_many)) as u64 & 0b1111;
+        fitting |= fits << (i - start);
+        i += 4;
+    }
+
+    if i < end {
+        fitting |= fitting_sets_portable(columns, i, end, letters, blanks) << (i - start);
+    }
+    return fitting;
+}
+
+/// Two sets at a time, which every x86_64 CPU can do
+#[cfg(target_arch = "x86_64")]
+unsafe fn fitting_sets_sse2(
+    columns: &[Vec<u64>; NUM_BLOCKS],
+    start: usize,
+    end: usize,
+    letters: &[u64; NUM_BLOCKS],
+    blanks: u32,
+) -> u64 {
+    use std::arch::x86_64::*;
+
+    let zero = _mm_setzero_si128();
+    let mut have = [zero; NUM_BLOCKS];
+    for (have, &letters) in have.iter_mut().zip(letters.iter()) {
+        *have = _mm_set1_epi64x(letters as i64);
+    }
+
+    let mut fitting = 0;
+    let mut i = start;
+    while i + 2 <= end {
+        let mut missing = zero;
+        for b in 0..NUM_BLOCKS {
+            let need = _mm_loadu_si128(columns[b].as_ptr().add(i) as *const __m128i);
+            let lanes = _mm_subs_epu8(need, have[b]);
+            missing = _mm_add_epi64(missing, _mm_sad_epu8(lanes, zero));
+        }
+
+        let mut sums = [0u64; 2];
+        _mm_storeu_si128(sums.as_mut_ptr() as *mut __m128i, missing);
+        for (j, &sum) in sums.iter().enumerate() {
+            if sum <= blanks as u64 {
+                fitting |= 1 << (i - start + j);
+            }
+        }
+        i += 2;
+    }
+
+    if i < end {
+        fitting |= fitting_sets_portable(columns, i, end, letters, blanks) << (i - start);
+    }
+    return fitting;
+}
+
+/// Two sets at a time, for WASM builds with `-C target-feature=+simd128`
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+fn fitting_sets_simd128(
+    columns: &[Vec<u64>; NUM_BLOCKS],
+    start: usize,
+    end: usize,
+    letters: &[u64; NUM_BLOCKS],
+    blanks: u32,
+) -> u64 {
+    use std::arch::wasm32::*;
+
+    let mut have = [u64x2_splat(0); NUM_BLOCKS];
+    for (have, &letters) in have.iter_mut().zip(letters.iter()) {
+        *have = u64x2_splat(letters);
+    }
+
+    let mut fitting = 0;
+    let mut i = start;
+    while i + 2 <= end {
+        let mut missing = u32x4_splat(0);
+        for b in 0..NUM_BLOCKS {
+            let need = unsafe { v128_load(columns[b].as_ptr().add(i) as *const v128) };
+            let lanes = u8x16_sub_sat(need, have[b]);
+            // no byte sums here, so widen the lanes twice to add them up in 32 bits
+            let sums = u32x4_extadd_pairwise_u16x8(u16x8_extadd_pairwise_u8x16(lanes));
+            missing = i32x4_add(missing, sums);
+        }
+
+        let first = u32x4_extract_lane::<0>(missing) + u32x4_extract_lane::<1>(missing);
+        let second = u32x4_extract_lane::<2>(missing) + u32x4_extract_lane::<3>(missing);
+        if first <= blanks {
+            fitting |= 1 << (i - start);
+        }
+        if second <= blanks {
+            fitting |= 1 << (i - start + 1);
+        }
+        i += 2;
+    }
+
+    if i < end {
+        fitting |= fitting_sets_portable(columns, i, end, letters, blanks) << (i - start);
+    }
+    return fitting;
+}
@@ -0,0 +1,791 @@
+use super::dag_searcher::{word_key, RankBound};
+use super::{AlphaMultiset, DAGSearcher, FilterBounds, WordSearcher, EMBEDDED_WORDLIST_CHECKSUM};
+use crate::{
+    ranking::TopK, Alphabet, Error, Filter, LookupOptions, LookupStats, Ranking, Result, Word,
+    WordRef, Wordlist,
+};
+use fixedbitset::FixedBitSet;
+use petgraph::prelude::*;
+use std::convert::TryInto;
+use std::path::Path;
+
+static EMBEDDED_INDEX: &[u8] = include_bytes!("dag.bin");
+
+/// First bytes of every index
+const MAGIC: &[u8; 8] = b"WSDAGIDX";
+/// Version of the layout, to be bumped whenever it changes
+const VERSION: u32 = 3;
+/// Magic, version, set size, the five `u32` counts and the two checksums
+const HEADER_LEN: usize = 44;
+/// The three `u64` of a node's `RankBound`
+const BOUND_BYTES: usize = 24;
+/// Where the checksum of everything following the header is stored
+const CHECKSUM_AT: usize = 40;
+
+/// A lookup DAG laid out flat in a byte slice, so it can be searched right where it's stored (like
+/// embedded in the binary or memory mapped from a file) instead of being deserialized first
+///
+/// The layout, with all integers in little endian:
+/// - header: magic, `u32` version, `u32` size of an `AlphaMultiset`, the `u32` counts of nodes,
+///   edges, words, arena bytes and alphabet bytes, the `u32` checksum of the wordlist the index was
+///   built from (see `Wordlist::checksum`), and the CRC32 of everything following the header
+/// - alphabet: its letters in UTF-8, a newline, then the two chars of each folding rule
+/// - sets: the `AlphaMultiset` of every node
+/// - bounds: `u64` highest frequency of any word in or reachable from every node, followed by the
+///   `u64` length and frequency of the longest (and then most frequent) of them
+/// - edge offsets: `u32` index of the first edge of every node, followed by the edge count
+/// - edges: `u32` target node of every edge
+/// - word offsets: `u32` index of the first word of every node, followed by the word count
+/// - frequencies: `u64` frequency of every word
+/// - word starts: `u32` start of every word in the arena, followed by the arena length
+/// - arena: all words in UTF-8, back to back
+///
+/// Node 0 is the empty set, from which lookups start, like in `DAGSearcher`.
+#[derive(Debug, Clone)]
+pub struct DAGIndex<B: AsRef<[u8]> = &'static [u8]> {
+    bytes: B,
+    alphabet: Alphabet,
+    node_count: usize,
+    sections: Sections,
+}
+
+/// Byte offsets of the sections following the header
+#[derive(Debug, Clone, Copy)]
+struct Sections {
+    sets: usize,
+    bounds: usize,
+    edge_offsets: usize,
+    edges: usize,
+    word_offsets: usize,
+    frequencies: usize,
+    word_starts: usize,
+    arena: usize,
+}
+
+impl Default for DAGIndex {
+    fn default() -> Self {
+        Self::embedded()
+    }
+}
+
+impl DAGIndex {
+    /// Use the index embedded at compile time (from "dag.bin" generated by `gen_files`)
+    pub fn embedded() -> Self {
+        Self::try_embedded().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `embedded`, but reports a stale "dag.bin" or one of another layout instead of
+    /// panicking
+    ///
+    /// Only its header is checked, against the checksum of the embedded wordlist taken at build
+    /// time, so startup doesn't have to read all of it. `verify` checks the rest.
+    pub fn try_embedded() -> Result<Self> {
+        let index = Self::try_from_header(EMBEDDED_INDEX)?;
+        if index.wordlist_checksum() != EMBEDDED_WORDLIST_CHECKSUM {
+            return Err(Error::StaleIndex {
+                found: index.wordlist_checksum(),
+                expected: EMBEDDED_WORDLIST_CHECKSUM,
+            });
+        }
+
+        Ok(index)
+    }
+}
+
+impl DAGIndex<Vec<u8>> {
+    /// Read an index file into memory
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| Error::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Self::try_from_bytes(bytes)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl DAGIndex<memmap2::Mmap> {
+    /// Memory map an index file, so only the parts touched by lookups are ever read
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let open_error = |source| Error::Open {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let file = std::fs::File::open(path).map_err(open_error)?;
+        // the index is only ever read, and modifying the file while it's mapped is on the caller,
+        // like for any other memory mapped file
+        let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(open_error)?;
+
+        Self::try_from_bytes(mmap)
+    }
+}
+
+impl<B: AsRef<[u8]>> DAGIndex<B> {
+    /// Use an index laid out like "dag.bin", checking its header, the sizes of its sections, its
+    /// checksum and that every offset in it is in range, so lookups can't run off its sections
+    pub fn try_from_bytes(bytes: B) -> Result<Self> {
+        let index = Self::try_from_header(bytes)?;
+        index.verify()?;
+
+        Ok(index)
+    }
+
+    /// Use an index after checking only its header and the sizes of its sections, for indices
+    /// which can be trusted not to be corrupt
+    fn try_from_header(bytes: B) -> Result<Self> {
+        let data = bytes.as_ref();
+        if data.len() < MAGIC.len() + 4 || &data[..MAGIC.len()] != MAGIC {
+            return Err(Error::CorruptIndex("not a lookup index"));
+        }
+
+        // the version comes right after the magic, so indices of any other layout get a helpful error
+        let version = read_u32(data, 8);
+        if version != VERSION {
+            return Err(Error::IndexLayout {
+                what: "layout version",
+                found: version,
+                expected: VERSION,
+            });
+        }
+        if data.len() < HEADER_LEN {
+            return Err(Error::CorruptIndex("header is cut short"));
+        }
+        // e.g. after `AlphaMultiset` got more lanes without the version being bumped
+        let set_bytes = read_u32(data, 12);
+        if set_bytes != AlphaMultiset::SET_BYTES as u32 {
+            return Err(Error::IndexLayout {
+                what: "set size",
+                found: set_bytes,
+                expected: AlphaMultiset::SET_BYTES as u32,
+            });
+        }
+
+        // sizes are added up in 64 bits, which can't overflow for `u32` counts even on wasm32
+        let count = |at| read_u32(data, at) as u64;
+        let (node_count, edge_count, word_count) = (count(16), count(20), count(24));
+        let sizes = [
+            count(32),
+            node_count * AlphaMultiset::SET_BYTES as u64,
+            node_count * BOUND_BYTES as u64,
+            (node_count + 1) * 4,
+            edge_count * 4,
+            (node_count + 1) * 4,
+            word_count * 8,
+            (word_count + 1) * 4,
+            count(28),
+        ];
+        if HEADER_LEN as u64 + sizes.iter().sum::<u64>() != data.len() as u64 {
+            return Err(Error::CorruptIndex(
+                "section sizes don't add up to its length",
+            ));
+        }
+
+        let mut offsets = [HEADER_LEN; 9];
+        for i in 1..offsets.len() {
+            offsets[i] = offsets[i - 1] + sizes[i - 1] as usize;
+        }
+        let [alphabet_start, sets, bounds, edge_offsets, edges, word_offsets, frequencies, word_starts, arena] =
+            offsets;
+        let (node_count, edge_count, word_count, arena_len) = (
+            node_count as usize,
+            edge_count as usize,
+            word_count as usize,
+            sizes[8] as usize,
+        );
+        if node_count == 0
+            || read_u32(data, edges - 4) as usize != edge_count
+            || read_u32(data, frequencies - 4) as usize != word_count
+            || read_u32(data, arena - 4) as usize != arena_len
+        {
+            return Err(Error::CorruptIndex(
+                "offsets don't match the counts of its header",
+            ));
+        }
+
+        let alphabet = decode_alphabet(&data[alphabet_start..sets])?;
+
+        Ok(Self {
+            bytes,
+            alphabet,
+            node_count,
+            sections: Sections {
+                sets,
+                bounds,
+                edge_offsets,
+                edges,
+                word_offsets,
+                frequencies,
+                word_starts,
+                arena,
+            },
+        })
+    }
+
+    /// Check the checksum of the whole index, and that none of its offsets point past the
+    /// sections they index into, its edges lead to nodes which exist and its words are UTF-8
+    ///
+    /// `try_from_bytes` already does, so this is only needed for the embedded index.
+    pub fn verify(&self) -> Result<()> {
+        let data = self.bytes.as_ref();
+        let (found, expected) = (
+            crc32fast::hash(&data[HEADER_LEN..]),
+            read_u32(data, CHECKSUM_AT),
+        );
+        if found != expected {
+            return Err(Error::IndexChecksum { found, expected });
+        }
+
+        let s = &self.sections;
+        let table = |start: usize, end: usize| {
+            (start..end)
+                .step_by(4)
+                .map(move |at| read_u32(data, at) as usize)
+        };
+
+        if !is_ascending(table(s.edge_offsets, s.edges), self.edge_count()) {
+            return Err(Error::CorruptIndex("edge offsets are out of order"));
+        }
+        if table(s.edges, s.word_offsets).any(|target| target >= self.node_count) {
+            return Err(Error::CorruptIndex("edge to a node which doesn't exist"));
+        }
+        if !is_ascending(table(s.word_offsets, s.frequencies), self.word_count()) {
+            return Err(Error::CorruptIndex("word offsets are out of order"));
+        }
+
+        let arena = std::str::from_utf8(&data[s.arena..])
+            .map_err(|_| Error::CorruptIndex("words aren't UTF-8"))?;
+        if !is_ascending(table(s.word_starts, s.arena), arena.len())
+            || !table(s.word_starts, s.arena).all(|start| arena.is_char_boundary(start))
+        {
+            return Err(Error::CorruptIndex("word starts are out of order"));
+        }
+
+        Ok(())
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn word_count(&self) -> usize {
+        read_u32(self.bytes.as_ref(), self.sections.frequencies - 4) as usize
+    }
+
+    fn edge_count(&self) -> usize {
+        read_u32(self.bytes.as_ref(), self.sections.edges - 4) as usize
+    }
+
+    /// Checksum of the wordlist this index was built from, or 0 if it isn't known
+    pub fn wordlist_checksum(&self) -> u32 {
+        read_u32(self.bytes.as_ref(), 36)
+    }
+
+    /// Report an index which wasn't built from `wordlist`, like a "dag.bin" left over from before
+    /// the wordlist changed
+    pub fn check_wordlist(&self, wordlist: &Wordlist) -> Result<()> {
+        let expected = wordlist.checksum()?;
+        if self.wordlist_checksum() != expected {
+            return Err(Error::StaleIndex {
+                found: self.wordlist_checksum(),
+                expected,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Copy the index into a `DAGSearcher`, e.g. to serialize it in another format
+    pub fn to_searcher(&self) -> DAGSearcher {
+        self.try_to_searcher().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `to_searcher`, but reports words which aren't UTF-8 instead of panicking
+    pub fn try_to_searcher(&self) -> Result<DAGSearcher> {
+        let mut dag = DiGraph::with_capacity(self.node_count, 0);
+
+        for nx in 0..self.node_count {
+            let words = self
+                .word_range(nx)
+                .map(|i| self.word(i).map(|w| w.to_word()))
+                .collect::<Result<_>>()?;
+            dag.add_node((self.set(nx), words));
+        }
+        for nx in 0..self.node_count {
+            // petgraph lists the latest edge of a node first, so add them in reverse to keep the order
+            let neighbors: Vec<_> = self.neighbors(nx).collect();
+            for ni in neighbors.into_iter().rev() {
+                dag.add_edge(NodeIndex::new(nx), NodeIndex::new(ni), ());
+            }
+        }
+
+        Ok(DAGSearcher::from_dag(
+            dag,
+            self.alphabet.clone(),
+            self.wordlist_checksum(),
+        ))
+    }
+
+    fn set(&self, nx: usize) -> AlphaMultiset {
+        let start = self.sections.sets + nx * AlphaMultiset::SET_BYTES;
+        AlphaMultiset::from_le_bytes(&self.bytes.as_ref()[start..start + AlphaMultiset::SET_BYTES])
+    }
+
+    fn bound(&self, nx: usize) -> RankBound {
+        let data = self.bytes.as_ref();
+        let at = self.sections.bounds + nx * BOUND_BYTES;
+
+        RankBound {
+            max_frequency: read_u64(data, at) as usize,
+            max_length_frequency: (
+                read_u64(data, at + 8) as usize,
+                read_u64(data, at + 16) as usize,
+            ),
+        }
+    }
+
+    fn neighbors(&self, nx: usize) -> impl Iterator<Item = usize> + '_ {
+        let data = self.bytes.as_ref();
+        let first = read_u32(data, self.sections.edge_offsets + nx * 4) as usize;
+        let end = read_u32(data, self.sections.edge_offsets + (nx + 1) * 4) as usize;
+
+        (first..end).map(move |e| read_u32(data, self.sections.edges + e * 4) as usize)
+    }
+
+    fn word_range(&self, nx: usize) -> std::ops::Range<usize> {
+        let data = self.bytes.as_ref();
+        let first = read_u32(data, self.sections.word_offsets + nx * 4) as usize;
+        let end = read_u32(data, self.sections.word_offsets + (nx + 1) * 4) as usize;
+
+        first..end
+    }
+
+    fn word(&self, i: usize) -> Result<WordRef<'_>> {
+        let data = self.bytes.as_ref();
+        let start = read_u32(data, self.sections.word_starts + i * 4) as usize;
+        let end = read_u32(data, self.sections.word_starts + (i + 1) * 4) as usize;
+        let frequency = read_u64(data, self.sections.frequencies + i * 8) as usize;
+
+        let word = data[self.sections.arena..]
+            .get(start..end)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .ok_or(Error::CorruptIndex("word isn't UTF-8"))?;
+
+        Ok(WordRef::from_pair(word, frequency))
+    }
+
+    /// Search from the empty set, skipping nodes whose words and successors can't match `prune`
+    fn lookup_iter_bounded(&self, word: &str, prune: FilterBounds) -> DAGIndexLookupIter<'_, B> {
+        let norm = self.alphabet.to_set(word);
+
+        let mut bfs_queue = std::collections::VecDeque::new();
+        if prune.is_satisfiable(&norm) {
+            bfs_queue.push_back(0);
+        }
+
+        DAGIndexLookupIter {
+            index: self,
+            norm,
+            prune,
+            visited: FixedBitSet::with_capacity(self.node_count),
+            bfs_queue,
+            words: 0..0,
+        }
+    }
+}
+
+impl<B: AsRef<[u8]>> WordSearcher for DAGIndex<B> {
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        Box::new(self.lookup_iter_bounded(word, FilterBounds::default()))
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the nodes visited
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut iter = self.lookup_iter_bounded(word, FilterBounds::default());
+            let words = iter.by_ref().map(Word::from).collect();
+            (words, iter.visited.count_ones(..))
+        })
+    }
+
+    /// Prunes nodes with banned letters or too many letters to still make a word of the filter's
+    /// length
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        Box::new(
+            self.lookup_iter_bounded(word, bounds)
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+
+    /// Best first search like `DAGSearcher::lookup_top_k`, using the bounds stored in the index
+    fn lookup_top_k(&self, word: &str, k: usize, ranking: Ranking) -> Vec<Word> {
+        let bound_key = |nx: usize| self.bound(nx).key(ranking);
+        if k == 0 || bound_key(0).is_none() {
+            return crate::ranking::top_k(self.lookup_iter(word), k, ranking);
+        }
+
+        let norm = self.alphabet.to_set(word);
+        let mut best = TopK::new(k, ranking);
+        let mut visited = FixedBitSet::with_capacity(self.node_count);
+        let mut frontier = std::collections::BinaryHeap::new();
+        frontier.push((bound_key(0), 0));
+
+        while let Some((bound, nx)) = frontier.pop() {
+            // ties can still be broken alphabetically, so only stop once strictly worse
+            if let Some(worst) = best.worst() {
+                if bound < word_key(worst, ranking) {
+                    break;
+                }
+            }
+
+            if visited.contains(nx) {
+                continue;
+            }
+            visited.put(nx);
+
+            if norm.has_subset(&self.set(nx)) {
+                for w in self.word_range(nx).filter_map(|i| self.word(i).ok()) {
+                    best.push(w);
+                }
+                frontier.extend(
+                    self.neighbors(nx)
+                        .filter(|&ni| !visited.contains(ni))
+                        .map(|ni| (bound_key(ni), ni)),
+                );
+            }
+        }
+
+        return best.into_sorted_vec();
+    }
+}
+
+/// Breadth first search from the empty set through all sets contained in `norm`
+struct DAGIndexLookupIter<'a, B: AsRef<[u8]>> {
+    index: &'a DAGIndex<B>,
+    norm: AlphaMultiset,
+    prune: FilterBounds,
+    visited: FixedBitSet,
+    bfs_queue: std::collections::VecDeque<usize>,
+    /// words of the last node found, which are yet to be returned
+    words: std::ops::Range<usize>,
+}
+
+impl<'a, B: AsRef<[u8]>> Iterator for DAGIndexLookupIter<'a, B> {
+    type Item = WordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(i) = self.words.next() {
+                // only an index which was never checked can have words which aren't UTF-8
+                match self.index.word(i) {
+                    Ok(word) => return Some(word),
+                    Err(_) => continue,
+                }
+            }
+
+            let nx = self.bfs_queue.pop_front()?;
+            if self.visited.contains(nx) {
+                continue;
+            }
+            self.visited.put(nx);
+
+            let set = self.index.set(nx);
+            if self.norm.has_subset(&set) && self.prune.admits_superset(&set) {
+                if self.prune.admits(&set) {
+                    self.words = self.index.word_range(nx);
+                }
+                self.bfs_queue.extend(self.index.neighbors(nx));
+            }
+        }
+    }
+}
+
+/// Lay out a lookup DAG as described on `DAGIndex`
+pub(super) fn encode(
+    dag: &DiGraph<(AlphaMultiset, Vec<Word>), ()>,
+    bounds: &[RankBound],
+    alphabet: &Alphabet,
+    wordlist_checksum: u32,
+) -> Vec<u8> {
+    let mut alphabet_bytes: String = alphabet.letters().iter().collect();
+    alphabet_bytes.push('\n');
+    for &(from, to) in alphabet.folding() {
+        alphabet_bytes.push(from);
+        alphabet_bytes.push(to);
+    }
+
+    let mut sets = Vec::with_capacity(dag.node_count() * AlphaMultiset::SET_BYTES);
+    let mut edge_offsets = Vec::with_capacity(dag.node_count() + 1);
+    let mut edges = Vec::with_capacity(dag.edge_count());
+    let mut word_offsets = Vec::with_capacity(dag.node_count() + 1);
+    let mut frequencies = Vec::new();
+    let mut word_starts = Vec::new();
+    let mut arena = String::new();
+
+    for nx in dag.node_indices() {
+        let (set, words) = &dag[nx];
+        set.write_le_bytes(&mut sets);
+
+        edge_offsets.push(edges.len());
+        edges.extend(dag.neighbors(nx).map(|ni| ni.index()));
+
+        word_offsets.push(frequencies.len());
+        for w in words {
+            frequencies.push(w.frequency() as u64);
+            word_starts.push(arena.len());
+            arena.push_str(w);
+        }
+    }
+    edge_offsets.push(edges.len());
+    word_offsets.push(frequencies.len());
+    word_starts.push(arena.len());
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    for &count in &[
+        AlphaMultiset::SET_BYTES,
+        dag.node_count(),
+        edges.len(),
+        frequencies.len(),
+        arena.len(),
+        alphabet_bytes.len(),
+    ] {
+        push_u32(&mut out, count);
+    }
+    out.extend_from_slice(&wordlist_checksum.to_le_bytes());
+    // filled in once everything else is laid out
+    out.extend_from_slice(&0u32.to_le_bytes());
+
+    out.extend_from_slice(alphabet_bytes.as_bytes());
+    out.extend_from_slice(&sets);
+    for bound in bounds {
+        let (length, frequency) = bound.max_length_frequency;
+        for &x in &[bound.max_frequency, length, frequency] {
+            out.extend_from_slice(&(x as u64).to_le_bytes());
+        }
+    }
+    for &x in edge_offsets.iter().chain(&edges).chain(&word_offsets) {
+        push_u32(&mut out, x);
+    }
+    for &f in &frequencies {
+        out.extend_from_slice(&f.to_le_bytes());
+    }
+    for &x in &word_starts {
+        push_u32(&mut out, x);
+    }
+    out.extend_from_slice(arena.as_bytes());
+
+    let checksum = crc32fast::hash(&out[HEADER_LEN..]);
+    out[CHECKSUM_AT..HEADER_LEN].copy_from_slice(&checksum.to_le_bytes());
+
+    return out;
+}
+
+fn decode_alphabet(bytes: &[u8]) -> Result<Alphabet> {
+    let text =
+        std::str::from_utf8(bytes).map_err(|_| Error::CorruptIndex("alphabet isn't UTF-8"))?;
+    let mut parts = text.splitn(2, '\n');
+    let (letters, folding) = match (parts.next(), parts.next()) {
+        (Some(letters), Some(folding)) => (letters, folding),
+        _ => {
+            return Err(Error::CorruptIndex(
+                "alphabet isn't followed by its folding rules",
+            ))
+        }
+    };
+
+    let folding: Vec<char> = folding.chars().collect();
+    if folding.len() % 2 != 0 {
+        return Err(Error::CorruptIndex(
+            "folding rule without a letter to fold onto",
+        ));
+    }
+
+    Ok(Alphabet::try_new(letters)?.with_folding(folding.chunks(2).map(|p| (p[0], p[1]))))
+}
+
+/// Whether `offsets` never go down, nor past `end`
+fn is_ascending<I: Iterator<Item = usize>>(offsets: I, end: usize) -> bool {
+    let mut last = 0;
+    for offset in offsets {
+        if offset < last || offset > end {
+            return false;
+        }
+        last = offset;
+    }
+
+    return true;
+}
+
+fn push_u32(out: &mut Vec<u8>, x: usize) {
+    assert!(x <= u32::MAX as usize, "lookup DAG too large for an index");
+    out.extend_from_slice(&(x as u32).to_le_bytes());
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: Vec<Word>) -> Vec<String> {
+        let mut words: Vec<_> = words.iter().map(|w| w.to_string()).collect();
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn test_index_matches_searcher() {
+        let wordlist = b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\ndab 1\n";
+        let searcher = DAGSearcher::from_wordlist(Wordlist::from_bytes(wordlist));
+        let bytes = searcher.to_index_bytes();
+        let index = DAGIndex::try_from_bytes(&*bytes).unwrap();
+
+        assert_eq!(index.word_count(), 9);
+        for letters in &["facedb", "fade", "ab?", "", "zzz"] {
+            assert_eq!(
+                sorted(index.lookup(letters)),
+                sorted(searcher.lookup(letters))
+            );
+            assert_eq!(
+                sorted(index.lookup_filter(letters, "___")),
+                sorted(searcher.lookup_filter(letters, "___"))
+            );
+        }
+
+        for &ranking in &[
+            Ranking::Frequency,
+            Ranking::LengthThenFrequency,
+            Ranking::Alphabetical,
+        ] {
+            for letters in &["facedb", "ab?", "", "zzz"] {
+                assert_eq!(
+                    index.lookup_top_k(letters, 3, ranking),
+                    searcher.lookup_top_k(letters, 3, ranking)
+                );
+            }
+        }
+
+        let copy = index.to_searcher();
+        assert_eq!(copy.to_index_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_index_keeps_alphabet() {
+        let wordlist = Wordlist::from_bytes("año 5\nCanción 2\n".as_bytes())
+            .with_alphabet(Alphabet::spanish());
+        let bytes = DAGSearcher::from_wordlist(wordlist).to_index_bytes();
+        let index = DAGIndex::try_from_bytes(bytes).unwrap();
+
+        assert_eq!(index.alphabet(), &Alphabet::spanish());
+        assert_eq!(sorted(index.lookup("ñoa")), vec!["año"]);
+    }
+
+    #[test]
+    fn test_bad_index() {
+        let wordlist = || Wordlist::from_bytes(b"ace 9\n");
+        let bytes = DAGSearcher::from_wordlist(wordlist()).to_index_bytes();
+
+        assert!(matches!(
+            DAGIndex::try_from_bytes(&b"garbage"[..]),
+            Err(Error::CorruptIndex(_))
+        ));
+        assert!(matches!(
+            DAGIndex::try_from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::CorruptIndex(_))
+        ));
+
+        let mut newer = bytes.clone();
+        newer[8] += 1;
+        assert!(matches!(
+            DAGIndex::try_from_bytes(newer),
+            Err(Error::IndexLayout {
+                what: "layout version",
+                ..
+            })
+        ));
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            DAGIndex::try_from_bytes(flipped),
+            Err(Error::IndexChecksum { .. })
+        ));
+
+        let index = DAGIndex::try_from_bytes(&*bytes).unwrap();
+        assert!(index.check_wordlist(&wordlist()).is_ok());
+        assert!(matches!(
+            index.check_wordlist(&Wordlist::from_bytes(b"ace 8\n")),
+            Err(Error::StaleIndex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_embedded_wordlist_checksum() {
+        // what the embedded index is checked against instead of the wordlist itself
+        assert_eq!(
+            Wordlist::embedded().checksum().unwrap(),
+            EMBEDDED_WORDLIST_CHECKSUM
+        );
+    }
+
+    #[test]
+    fn test_malformed_index() {
+        let wordlist = b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\ndab 1\n";
+        let bytes = DAGSearcher::from_wordlist(Wordlist::from_bytes(wordlist)).to_index_bytes();
+        let index = DAGIndex::try_from_bytes(&*bytes).unwrap();
+        let s = index.sections;
+
+        // like a buggy generator would write them, with a checksum that matches
+        let malformed = |at: usize, patch: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[at..at + patch.len()].copy_from_slice(patch);
+            let checksum = crc32fast::hash(&bytes[HEADER_LEN..]);
+            bytes[CHECKSUM_AT..HEADER_LEN].copy_from_slice(&checksum.to_le_bytes());
+            DAGIndex::try_from_bytes(bytes)
+        };
+        let past_end = u32::MAX.to_le_bytes();
+
+        for &at in &[
+            s.edge_offsets + 4,
+            s.edges,
+            s.word_offsets + 4,
+            s.word_starts + 4,
+        ] {
+            assert!(matches!(
+                malformed(at, &past_end),
+                Err(Error::CorruptIndex(_))
+            ));
+        }
+        assert!(matches!(
+            malformed(s.edges, &(index.node_count() as u32).to_le_bytes()),
+            Err(Error::CorruptIndex(_))
+        ));
+        assert!(matches!(
+            malformed(s.arena, &[0xff]),
+            Err(Error::CorruptIndex(_))
+        ));
+    }
+}
@@ -1,18 +1,45 @@
 use super::{
-    embedded_wordlist_iter, iter_to_wordmap, path_to_iter, str_to_set, AlphaMultiset, WordSearcher,
+    try_edited_word, try_wordlist_to_wordmap, AlphaMultiset, DAGIndex, EditableWordSearcher,
+    FilterBounds, WordSearcher,
+};
+use crate::{
+    ranking::TopK, Alphabet, Filter, LookupOptions, LookupStats, Ranking, Result, Word, WordRef,
+    Wordlist,
 };
-use crate::Word;
 use fixedbitset::FixedBitSet;
 use petgraph::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-
-static EMBEDDED_DAG: &'static [u8] = include_bytes!("dag.bin");
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DAGSearcher {
     // NOTE: rolling own DiGraph barely saves any space
     dag: DiGraph<(AlphaMultiset, Vec<Word>), ()>,
+    /// best word reachable from each node, indexed by node, for ranked lookups
+    bounds: Vec<RankBound>,
+    alphabet: Alphabet,
+    /// checksum of the wordlist this index was built from, or 0 if it isn't known
+    wordlist_checksum: u32,
+}
+
+/// Best ranking keys of any word in a node or the nodes reachable from it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(super) struct RankBound {
+    pub(super) max_frequency: usize,
+    /// lexicographically largest `(length, frequency)`
+    pub(super) max_length_frequency: (usize, usize),
+}
+
+impl RankBound {
+    /// Ranking key of the best word of the node or reachable from it, comparable to `word_key`, or
+    /// `None` if words can't be ranked by a bound
+    pub(super) fn key(&self, ranking: Ranking) -> Option<(usize, usize)> {
+        match ranking {
+            Ranking::Frequency => Some((self.max_frequency, 0)),
+            Ranking::LengthThenFrequency => Some(self.max_length_frequency),
+            Ranking::Alphabetical => None,
+        }
+    }
 }
 
 impl Default for DAGSearcher {
@@ -23,124 +50,579 @@ impl Default for DAGSearcher {
 
 impl DAGSearcher {
     /// Construct lookup index from a wordlist file
-    pub fn from_wordlist<P: AsRef<std::path::Path>>(path: P) -> Self {
-        Self {
-            dag: build_dag(iter_to_wordmap(path_to_iter(path))),
-        }
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Construct lookup index from a wordlist file, reporting unreadable files or malformed lines
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let wordlist = wordlist.into();
+        let wordlist_checksum = wordlist.checksum()?;
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist)?;
+        Ok(Self::from_dag(
+            build_dag(wordmap),
+            alphabet,
+            wordlist_checksum,
+        ))
     }
 
     pub fn from_embedded_wordlist() -> Self {
+        Self::from_wordlist(Wordlist::embedded())
+    }
+
+    /// Construct lookup index from the contents of a wordlist file, e.g. one fetched at runtime
+    pub fn try_from_wordlist_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::try_from_wordlist(Wordlist::from_bytes(bytes))
+    }
+
+    pub(super) fn from_dag(
+        dag: DiGraph<(AlphaMultiset, Vec<Word>), ()>,
+        alphabet: Alphabet,
+        wordlist_checksum: u32,
+    ) -> Self {
+        let bounds = build_bounds(&dag);
         Self {
-            dag: build_dag(iter_to_wordmap(embedded_wordlist_iter())),
+            dag,
+            bounds,
+            alphabet,
+            wordlist_checksum,
+        }
+    }
+
+    /// Search from the empty set, skipping nodes whose words and successors can't match `prune`
+    fn lookup_iter_bounded(&self, word: &str, prune: FilterBounds) -> DAGLookupIter<'_> {
+        let empty_set_node = NodeIndex::from(0);
+        let norm = self.alphabet.to_set(word);
+
+        let mut bfs_queue = std::collections::VecDeque::new();
+        if prune.is_satisfiable(&norm) {
+            bfs_queue.push_back(empty_set_node);
+        }
+
+        DAGLookupIter {
+            dag: &self.dag,
+            norm,
+            prune,
+            visited: FixedBitSet::with_capacity(self.dag.node_count()),
+            bfs_queue,
+            words: [].iter(),
         }
     }
 
     /// Use embedded wordlist binary (from "dag.bin" generated by `gen_files`)
+    ///
+    /// `DAGIndex::embedded` can search it without copying it first.
     pub fn from_embedded_dag() -> Self {
-        bincode::deserialize(EMBEDDED_DAG).unwrap()
+        Self::try_from_embedded_dag().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `from_embedded_dag`, but reports a stale or corrupt "dag.bin" instead of panicking
+    pub fn try_from_embedded_dag() -> Result<Self> {
+        DAGIndex::try_embedded()?.try_to_searcher()
+    }
+
+    /// Use a lookup index laid out like "dag.bin", e.g. one fetched at runtime, reporting one of
+    /// another layout version or which doesn't match its checksum
+    pub fn try_from_dag_bytes(bytes: &[u8]) -> Result<Self> {
+        DAGIndex::try_from_bytes(bytes)?.try_to_searcher()
+    }
+
+    /// Lay this index out like "dag.bin", to be searched in place by a `DAGIndex`
+    pub fn to_index_bytes(&self) -> Vec<u8> {
+        super::dag_index::encode(
+            &self.dag,
+            &self.bounds,
+            &self.alphabet,
+            self.wordlist_checksum,
+        )
+    }
+
+    /// The largest sets in the DAG which are subsets of `set` (including `set` itself, if it's in
+    /// the DAG), or the empty set node if there are none
+    fn largest_subsets(&self, set: &AlphaMultiset) -> Vec<NodeIndex> {
+        self.extreme_sets(NodeIndex::from(0), Outgoing, |other| set.has_subset(other))
+    }
+
+    /// The smallest sets in the DAG which are supersets of `set`, or the universal set node if there
+    /// are none
+    fn smallest_supersets(&self, set: &AlphaMultiset) -> Vec<NodeIndex> {
+        self.extreme_sets(NodeIndex::from(1), Incoming, |other| other.has_subset(set))
+    }
+
+    /// Breadth first search from `start` in direction `dir` through the nodes whose sets are
+    /// `related`, keeping the ones without any such neighbors
+    fn extreme_sets<F: Fn(&AlphaMultiset) -> bool>(
+        &self,
+        start: NodeIndex,
+        dir: Direction,
+        related: F,
+    ) -> Vec<NodeIndex> {
+        let mut visited = FixedBitSet::with_capacity(self.dag.node_count());
+        let mut bfs_queue = std::collections::VecDeque::new();
+        let mut extreme = Vec::new();
+        bfs_queue.push_back(start);
+        visited.put(start.index());
+
+        while let Some(nx) = bfs_queue.pop_front() {
+            let mut is_extreme = true;
+
+            for ni in self.dag.neighbors_directed(nx, dir) {
+                if related(&self.dag[ni].0) {
+                    is_extreme = false;
+                    if !visited.put(ni.index()) {
+                        bfs_queue.push_back(ni);
+                    }
+                }
+            }
+
+            if is_extreme {
+                extreme.push(nx);
+            }
+        }
+
+        return extreme;
+    }
+
+    /// Add a node for `set`, between its largest subsets and smallest supersets
+    fn insert_node(&mut self, set: AlphaMultiset) -> NodeIndex {
+        let subsets = self.largest_subsets(&set);
+        let supersets = self.smallest_supersets(&set);
+
+        // the new set is in between, so none of these are right above one another anymore
+        for &sub in subsets.iter() {
+            for &sup in supersets.iter() {
+                if let Some(edge) = self.dag.find_edge(sub, sup) {
+                    self.dag.remove_edge(edge);
+                }
+            }
+        }
+
+        let nx = self.dag.add_node((set, Vec::new()));
+        self.bounds.push(RankBound::default());
+        for sub in subsets {
+            self.dag.add_edge(sub, nx, ());
+        }
+        for sup in supersets {
+            self.dag.add_edge(nx, sup, ());
+        }
+
+        return nx;
+    }
+
+    /// Remove node `nx`, connecting its predecessors to the successors which are now right above
+    /// them
+    fn remove_node(&mut self, nx: NodeIndex) {
+        let empty_set_node = NodeIndex::from(0);
+        let universal_set_node = NodeIndex::from(1);
+        // removing a node moves the last node into its place
+        let last = NodeIndex::new(self.dag.node_count() - 1);
+        let moved = |ni: NodeIndex| if ni == last { nx } else { ni };
+
+        let preds: Vec<_> = self
+            .dag
+            .neighbors_directed(nx, Incoming)
+            .map(moved)
+            .collect();
+        let succs: Vec<_> = self.dag.neighbors(nx).map(moved).collect();
+        self.dag.remove_node(nx);
+        self.bounds.swap_remove(nx.index());
+
+        for &pred in preds.iter() {
+            for &succ in succs.iter() {
+                // like in `build_dag`, the empty and universal set are only connected through sets
+                if pred == empty_set_node && succ == universal_set_node {
+                    continue;
+                }
+
+                let succ_set = &self.dag[succ].0;
+                let reachable = self
+                    .dag
+                    .neighbors(pred)
+                    .any(|ni| succ_set.has_subset(&self.dag[ni].0));
+                if !reachable {
+                    self.dag.add_edge(pred, succ, ());
+                }
+            }
+        }
+
+        self.update_bounds(preds);
+    }
+
+    /// Recompute the bounds of `changed`, and of any nodes leading to a node whose bounds change
+    fn update_bounds(&mut self, changed: Vec<NodeIndex>) {
+        let mut stack = changed;
+
+        while let Some(nx) = stack.pop() {
+            let bound = node_bound(&self.dag, &self.bounds, nx);
+            if bound != self.bounds[nx.index()] {
+                self.bounds[nx.index()] = bound;
+                stack.extend(self.dag.neighbors_directed(nx, Incoming));
+            }
+        }
     }
 }
 
 impl WordSearcher for DAGSearcher {
-    fn lookup(&self, word: &str) -> Vec<Word> {
-        let norm = str_to_set(word);
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        Box::new(self.lookup_iter_bounded(word, FilterBounds::default()))
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the nodes visited
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut iter = self.lookup_iter_bounded(word, FilterBounds::default());
+            let words = iter.by_ref().map(Word::from).collect();
+            (words, iter.visited.count_ones(..))
+        })
+    }
+
+    /// Prunes nodes with banned letters or too many letters to still make a word of the filter's
+    /// length
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let filter = filter.clone();
+
+        Box::new(
+            self.lookup_iter_bounded(word, FilterBounds::new(&filter, options, &self.alphabet))
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+
+    /// Best first search, always expanding the node with the best word reachable from it, until
+    /// no unexpanded node can reach a word better than the `k` found so far
+    fn lookup_top_k(&self, word: &str, k: usize, ranking: Ranking) -> Vec<Word> {
+        let bound_key = |nx: NodeIndex| self.bounds[nx.index()].key(ranking);
 
         let empty_set_node = NodeIndex::from(0);
+        if k == 0 || bound_key(empty_set_node).is_none() {
+            return crate::ranking::top_k(self.lookup_iter(word), k, ranking);
+        }
 
+        let norm = self.alphabet.to_set(word);
+        let mut best = TopK::new(k, ranking);
         let mut visited = FixedBitSet::with_capacity(self.dag.node_count());
-        let mut bfs_queue = std::collections::VecDeque::new();
-        let mut found_words = Vec::new();
+        let mut frontier = std::collections::BinaryHeap::new();
+        frontier.push((bound_key(empty_set_node), empty_set_node));
 
-        bfs_queue.push_back(empty_set_node);
+        while let Some((bound, nx)) = frontier.pop() {
+            // ties can still be broken alphabetically, so only stop once strictly worse
+            if let Some(worst) = best.worst() {
+                if bound < word_key(worst, ranking) {
+                    break;
+                }
+            }
 
-        while let Some(nx) = bfs_queue.pop_front() {
             if visited.contains(nx.index()) {
                 continue;
             }
             visited.put(nx.index());
 
             if norm.has_subset(&self.dag[nx].0) {
-                found_words.extend_from_slice(&*self.dag[nx].1);
-                bfs_queue.extend(self.dag.neighbors(nx));
+                for w in self.dag[nx].1.iter() {
+                    best.push(w.into());
+                }
+                frontier.extend(
+                    self.dag
+                        .neighbors(nx)
+                        .filter(|ni| !visited.contains(ni.index()))
+                        .map(|ni| (bound_key(ni), ni)),
+                );
             }
         }
 
-        return found_words;
+        return best.into_sorted_vec();
     }
 }
 
-/// Build a lookup DAG with special root node at index 0 for easy lookups
-fn build_dag(
-    mut words: HashMap<AlphaMultiset, Vec<Word>>,
-) -> DiGraph<(AlphaMultiset, Vec<Word>), ()> {
-    let mut dag = DiGraph::<_, ()>::default();
-    let empty_set_node = dag.add_node((AlphaMultiset::new_empty(), Vec::new()));
-    let universal_set_node = dag.add_node((AlphaMultiset::new_universal(), Vec::new()));
-
-    // keep track of nodes which have no outgoing edges (sinks), to be connected to the empty set later
-    let mut sinks = HashSet::new();
+/// Nodes are added or removed as the first word with their letters is added or the last one
+/// removed, keeping every set connected to its largest subsets and smallest supersets, so lookups
+/// find the same words as in a DAG built with the words from the start
+impl EditableWordSearcher for DAGSearcher {
+    fn try_insert(&mut self, word: &str, frequency: usize) -> Result<()> {
+        let (word, set) = match try_edited_word(word, &self.alphabet)? {
+            Some(edited) => edited,
+            None => return Ok(()),
+        };
 
-    let mut i = 0;
-    while !words.is_empty() {
-        if i > 0 && i % 10_000 == 0 {
-            println!("Inserted {} words", i);
+        let nx = match self.largest_subsets(&set)[..] {
+            [nx] if self.dag[nx].0 == set => nx,
+            _ => self.insert_node(set),
+        };
+        let words = &mut self.dag[nx].1;
+        match words.iter_mut().find(|w| ***w == *word) {
+            Some(w) => *w = Word::from_pair(word, frequency),
+            None => words.push(Word::from_pair(word, frequency)),
         }
-        i += 1;
 
-        // find a set which is not contained in any other set
-        let best = {
-            let mut word_iter = words.keys();
-            let mut best = word_iter.next().unwrap();
-            while let Some(cur) = word_iter.next() {
-                if cur.has_subset(best) {
-                    best = cur;
-                }
-            }
-            best.clone()
+        self.update_bounds(vec![nx]);
+        Ok(())
+    }
+
+    fn remove(&mut self, word: &str) -> bool {
+        let (word, set) = match try_edited_word(word, &self.alphabet) {
+            Ok(Some(edited)) => edited,
+            _ => return false,
+        };
+        let nx = match self.largest_subsets(&set)[..] {
+            [nx] if self.dag[nx].0 == set => nx,
+            _ => return false,
         };
 
-        // finds smallest set that still contain "best"
-        let mut visited = FixedBitSet::with_capacity(dag.node_count());
-        let mut bfs_queue = std::collections::VecDeque::new();
-        let mut containing_nodes = Vec::new();
-        bfs_queue.push_back(universal_set_node);
+        let words = &mut self.dag[nx].1;
+        let len = words.len();
+        words.retain(|w| **w != *word);
+        if words.len() == len {
+            return false;
+        }
 
-        // search through existing DAG to find smallest sets which still contain "best"
-        while let Some(nx) = bfs_queue.pop_front() {
-            if visited.contains(nx.index()) {
+        if words.is_empty() {
+            self.remove_node(nx);
+        } else {
+            self.update_bounds(vec![nx]);
+        }
+        return true;
+    }
+}
+
+/// Ranking key of a word, comparable to the bound keys of `lookup_top_k`
+pub(super) fn word_key(word: WordRef, ranking: Ranking) -> Option<(usize, usize)> {
+    match ranking {
+        Ranking::Frequency => Some((word.frequency(), 0)),
+        Ranking::LengthThenFrequency => Some((word.chars().count(), word.frequency())),
+        Ranking::Alphabetical => None,
+    }
+}
+
+/// Compute the best word reachable from every node, visiting nodes after all their neighbors
+fn build_bounds(dag: &DiGraph<(AlphaMultiset, Vec<Word>), ()>) -> Vec<RankBound> {
+    let mut bounds = vec![RankBound::default(); dag.node_count()];
+    let order = petgraph::algo::toposort(dag, None).expect("lookup DAG has a cycle");
+
+    for nx in order.into_iter().rev() {
+        bounds[nx.index()] = node_bound(dag, &bounds, nx);
+    }
+
+    return bounds;
+}
+
+/// The best word in node `nx` or reachable from it, given the `bounds` of its neighbors
+fn node_bound(
+    dag: &DiGraph<(AlphaMultiset, Vec<Word>), ()>,
+    bounds: &[RankBound],
+    nx: NodeIndex,
+) -> RankBound {
+    let mut bound = RankBound::default();
+
+    for w in dag[nx].1.iter() {
+        bound.max_frequency = bound.max_frequency.max(w.frequency());
+        bound.max_length_frequency = bound
+            .max_length_frequency
+            .max((w.chars().count(), w.frequency()));
+    }
+    for ni in dag.neighbors(nx) {
+        let child = &bounds[ni.index()];
+        bound.max_frequency = bound.max_frequency.max(child.max_frequency);
+        bound.max_length_frequency = bound.max_length_frequency.max(child.max_length_frequency);
+    }
+
+    return bound;
+}
+
+/// Breadth first search from the empty set through all sets contained in `norm`
+struct DAGLookupIter<'a> {
+    dag: &'a DiGraph<(AlphaMultiset, Vec<Word>), ()>,
+    norm: AlphaMultiset,
+    prune: FilterBounds,
+    visited: FixedBitSet,
+    bfs_queue: std::collections::VecDeque<NodeIndex>,
+    /// words of the last node found, which are yet to be returned
+    words: std::slice::Iter<'a, Word>,
+}
+
+impl<'a> Iterator for DAGLookupIter<'a> {
+    type Item = WordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.words.next() {
+                return Some(word.into());
+            }
+
+            let nx = self.bfs_queue.pop_front()?;
+            if self.visited.contains(nx.index()) {
                 continue;
             }
-            visited.put(nx.index());
+            self.visited.put(nx.index());
 
-            if !dag.neighbors(nx).any(|ni| dag[ni].0.has_subset(&best)) {
-                containing_nodes.push(nx);
-            } else {
-                bfs_queue.extend(dag.neighbors(nx).filter(|&ni| dag[ni].0.has_subset(&best)));
+            let set = &self.dag[nx].0;
+            if self.norm.has_subset(set) && self.prune.admits_superset(set) {
+                if self.prune.admits(set) {
+                    self.words = self.dag[nx].1.iter();
+                }
+                self.bfs_queue.extend(self.dag.neighbors(nx));
             }
         }
+    }
+}
+
+/// Build a lookup DAG with special root node at index 0 for easy lookups
+///
+/// Every set gets an edge from each of its largest proper subsets (or the empty set if it has
+/// none), and every set without supersets an edge to the universal set at index 1. Rather than
+/// searching the DAG for the subsets of a set, they're looked up by the letters they contain, as
+/// those have to be some of the letters of the set.
+fn build_dag(words: HashMap<AlphaMultiset, Vec<Word>>) -> DiGraph<(AlphaMultiset, Vec<Word>), ()> {
+    let mut words: Vec<_> = words.into_iter().collect();
+    // ties are broken by the counts themselves, so the same wordlist always gives the same DAG
+    words.sort_unstable_by(|(a, _), (b, _)| {
+        a.len()
+            .cmp(&b.len())
+            .then_with(|| a.as_ref().cmp(b.as_ref()))
+    });
 
-        // add "best" to DAG
-        let new_nx = dag.add_node(words.remove_entry(&best).unwrap());
+    let mut dag = DiGraph::<_, ()>::with_capacity(words.len() + 2, words.len() * 2);
+    let empty_set_node = dag.add_node((AlphaMultiset::new_empty(), Vec::new()));
+    let universal_set_node = dag.add_node((AlphaMultiset::new_universal(), Vec::new()));
+
+    // nodes of the sets containing exactly the given letters, smallest first
+    let mut by_letters: HashMap<u64, Vec<NodeIndex>> = HashMap::new();
+    for (set, set_words) in words {
+        let letters = letter_mask(&set);
+        let nx = dag.add_node((set, set_words));
+        by_letters.entry(letters).or_default().push(nx);
+    }
 
-        // add "best" as potential sink
-        sinks.insert(new_nx);
+    let mut subsets = Vec::new();
+    let mut largest_subsets: Vec<NodeIndex> = Vec::new();
 
-        // connect shortest words found above to "best" node
-        for parent in containing_nodes {
-            dag.add_edge(parent, new_nx, ());
+    for nx in dag.node_indices().skip(2).collect::<Vec<_>>() {
+        let set = &dag[nx].0;
+        let letters = letter_mask(set);
 
-            // since these nodes have outgoing edges, remove them from sinks
-            sinks.remove(&parent);
+        // go through every subset of the letters, down to (and including) none at all
+        let mut sub_letters = letters;
+        loop {
+            if let Some(candidates) = by_letters.get(&sub_letters) {
+                subsets.extend(
+                    candidates
+                        .iter()
+                        .filter(|&&ni| ni != nx && set.has_subset(&dag[ni].0)),
+                );
+            }
+            if sub_letters == 0 {
+                break;
+            }
+            sub_letters = (sub_letters - 1) & letters;
+        }
+
+        // nodes were added smallest first, so a subset can only be contained in ones before it
+        subsets.sort_unstable_by(|a: &NodeIndex, b| b.cmp(a));
+        for ni in subsets.drain(..) {
+            if !largest_subsets
+                .iter()
+                .any(|&li| dag[li].0.has_subset(&dag[ni].0))
+            {
+                largest_subsets.push(ni);
+            }
         }
-    }
 
-    // connect empty set node to list of sinks
-    for sink in sinks.into_iter() {
-        dag.add_edge(sink, empty_set_node, ());
+        if largest_subsets.is_empty() {
+            largest_subsets.push(empty_set_node);
+        }
+        for ni in largest_subsets.drain(..) {
+            dag.add_edge(ni, nx, ());
+        }
     }
 
-    // finally, reverse graph edges so that it can be searched efficiently
-    dag.reverse();
+    // connect sets which aren't contained in any other set to the universal set
+    let maximal: Vec<_> = dag
+        .node_indices()
+        .skip(2)
+        .filter(|&nx| dag.neighbors(nx).next().is_none())
+        .collect();
+    for nx in maximal {
+        dag.add_edge(nx, universal_set_node, ());
+    }
 
     return dag;
 }
+
+/// The letters occurring in `set`, one bit each
+fn letter_mask(set: &AlphaMultiset) -> u64 {
+    set.char_counts()
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: Vec<Word>) -> Vec<String> {
+        let mut words: Vec<_> = words.iter().map(|w| w.to_string()).collect();
+        words.sort();
+        words
+    }
+
+    /// Edges by the sets they connect, which don't depend on the order nodes were added in
+    fn edges(searcher: &DAGSearcher) -> Vec<(String, String)> {
+        let dag = &searcher.dag;
+        let mut edges: Vec<_> = dag
+            .edge_indices()
+            .map(|e| {
+                let (a, b) = dag.edge_endpoints(e).unwrap();
+                (format!("{:?}", dag[a].0), format!("{:?}", dag[b].0))
+            })
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut dag = DAGSearcher::from_wordlist(Wordlist::from_bytes(
+            b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\n",
+        ));
+        let rebuilt = DAGSearcher::from_wordlist(Wordlist::from_bytes(
+            b"ace 9\ncafe 1\nfaced 6\nfade 4\nbead 3\nbad 2\ndab 20\nabed 30\n",
+        ));
+
+        dag.insert("bead", 3);
+        dag.insert("bad", 2);
+        dag.insert("Dab", 20);
+        dag.insert("abed", 30);
+        dag.insert("cafe", 1);
+        assert!(dag.remove("face"));
+        assert!(dag.remove("deaf"));
+        assert!(!dag.remove("deaf"));
+        assert!(!dag.remove("zzz"));
+        assert!(dag.try_insert("da?", 1).is_err());
+
+        assert_eq!(edges(&dag), edges(&rebuilt));
+        for letters in &["facedb", "fade", "ab?", "", "zzz"] {
+            assert_eq!(sorted(dag.lookup(letters)), sorted(rebuilt.lookup(letters)));
+            for &ranking in &[Ranking::Frequency, Ranking::LengthThenFrequency] {
+                assert_eq!(
+                    dag.lookup_top_k(letters, 2, ranking),
+                    rebuilt.lookup_top_k(letters, 2, ranking)
+                );
+            }
+        }
+
+        for word in &["ace", "cafe", "faced", "fade", "bead", "bad", "dab", "abed"] {
+            assert!(dag.remove(word));
+        }
+        assert_eq!(dag.dag.node_count(), 2);
+        assert_eq!(dag.dag.edge_count(), 0);
+    }
+}
@@ -1,13 +1,22 @@
 use super::{
-    embedded_wordlist_iter, iter_to_wordmap, path_to_iter, str_to_set, AlphaMultiset, WordSearcher,
+    try_wordlist_to_wordmap, wordlist_to_wordmap, AlphaMultiset, FilterBounds, WordSearcher,
+    MAX_LETTERS,
 };
-use crate::Word;
+use crate::{Alphabet, Filter, LookupOptions, LookupStats, Result, Word, WordRef, Wordlist};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
+/// Enumerates the subsets of the looked up letters, only going on with the counts of the first few
+/// letters which some set in the index starts with
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExpSearcher {
-    wordmap: HashMap<AlphaMultiset, Vec<Word>>,
+    /// every set, in order of the count of the first letter, then of the second and so on, so the
+    /// sets starting with the same counts are next to each other
+    sets: Vec<AlphaMultiset>,
+    /// words of every set, in the same order
+    words: Vec<Vec<Word>>,
+    alphabet: Alphabet,
 }
 
 impl Default for ExpSearcher {
@@ -17,80 +26,353 @@ impl Default for ExpSearcher {
 }
 
 impl ExpSearcher {
-    pub fn from_wordlist<P: AsRef<std::path::Path>>(path: P) -> Self {
-        Self {
-            wordmap: iter_to_wordmap(path_to_iter(path)),
-        }
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist.into())?;
+        Ok(Self::from_wordmap(wordmap, alphabet))
     }
     pub fn from_embedded_wordlist() -> Self {
+        let (wordmap, alphabet) = wordlist_to_wordmap(Wordlist::embedded());
+        Self::from_wordmap(wordmap, alphabet)
+    }
+
+    fn from_wordmap(wordmap: HashMap<AlphaMultiset, Vec<Word>>, alphabet: Alphabet) -> Self {
+        let mut entries: Vec<_> = wordmap.into_iter().collect();
+        entries.sort_by_cached_key(|(set, _)| set.char_counts());
+        let (sets, words) = entries.into_iter().unzip();
+
         Self {
-            wordmap: iter_to_wordmap(embedded_wordlist_iter()),
+            sets,
+            words,
+            alphabet,
+        }
+    }
+
+    /// Enumerate the subsets of `set` in the index which could still make a word matching `bounds`
+    fn subsets(&self, set: AlphaMultiset, bounds: &FilterBounds) -> Subsets<'_> {
+        let stack = if bounds.is_satisfiable(&set) && !self.sets.is_empty() {
+            vec![SubsetFrame {
+                letter: 0,
+                start: 0,
+                end: self.sets.len(),
+                blanks: set.blanks(),
+                len: 0,
+                budget: bounds.max_len,
+            }]
+        } else {
+            Vec::new()
+        };
+
+        let counts = set.char_counts();
+        let mut rest = [0; MAX_LETTERS + 1];
+        for letter in (0..MAX_LETTERS).rev() {
+            rest[letter] = rest[letter + 1] + counts[letter] as usize;
+        }
+
+        Subsets {
+            sets: &self.sets,
+            letters: self.alphabet.len(),
+            counts,
+            rest,
+            min_len: bounds.min_len,
+            needed: bounds.required.char_counts(),
+            banned: bounds.banned.char_counts(),
+            stack,
+            steps: 0,
         }
     }
 }
 
 impl WordSearcher for ExpSearcher {
-    /// Enumerate all unique subsets of the word multiset and do a table lookup on each of them
-    fn lookup(&self, word: &str) -> Vec<Word> {
-        let letter_set = str_to_set(word);
-        let mut words = Vec::new();
-
-        for subset in enum_subsets(letter_set) {
-            if let Some(matches) = self.wordmap.get(&subset) {
-                words.extend_from_slice(matches);
-            }
-        }
+    /// Enumerate the subsets of the word multiset which are in the index
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let letter_set = self.alphabet.to_set(word);
+
+        Box::new(
+            self.subsets(letter_set, &FilterBounds::default())
+                .flat_map(move |i| self.words[i].iter().map(WordRef::from)),
+        )
+    }
 
-        return words;
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
     }
+
+    /// Counts the subsets probed, as the counts of the first few letters they start with
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut subsets = self.subsets(self.alphabet.to_set(word), &FilterBounds::default());
+            let words = subsets
+                .by_ref()
+                .flat_map(|i| self.words[i].iter().cloned())
+                .collect();
+            (words, subsets.steps)
+        })
+    }
+
+    /// Only enumerates subsets containing the required letters and none of the banned ones, up to
+    /// the filter's length
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let letter_set = self.alphabet.to_set(word);
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        Box::new(
+            self.subsets(letter_set, &bounds)
+                .flat_map(move |i| self.words[i].iter().map(WordRef::from))
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+}
+
+/// Count of `letter` in `set`
+fn count(set: &AlphaMultiset, letter: usize) -> usize {
+    set.as_ref().get(letter).unwrap_or(0) as usize
+}
+
+/// Depth first enumeration of the subsets of the looked up letters which are in the index, as
+/// indices into its sets
+///
+/// Subsets are made up a letter at a time, and only with counts of that letter which some set
+/// in the index has after the counts chosen so far.
+struct Subsets<'a> {
+    sets: &'a [AlphaMultiset],
+    /// letters which blanks can stand for
+    letters: usize,
+    /// the looked up letters
+    counts: [u8; MAX_LETTERS],
+    /// number of looked up letters from each letter on
+    rest: [usize; MAX_LETTERS + 1],
+    /// the fewest letters a subset may have
+    min_len: usize,
+    /// letters which a subset must contain
+    needed: [u8; MAX_LETTERS],
+    /// letters a subset may not contain
+    banned: [u8; MAX_LETTERS],
+    /// subsets left to make up
+    stack: Vec<SubsetFrame>,
+    /// subsets probed so far
+    steps: usize,
 }
 
-fn enum_subsets(set: AlphaMultiset) -> Vec<AlphaMultiset> {
-    enum_subsets_impl(&mut set.char_counts(), 0)
+/// The counts of the letters before `letter` chosen so far, as the range of the index's sets which
+/// start with them
+#[derive(Clone, Copy)]
+struct SubsetFrame {
+    letter: usize,
+    start: usize,
+    end: usize,
+    /// blanks not yet used up
+    blanks: usize,
+    /// letters of the counts chosen so far
+    len: usize,
+    /// the most letters the rest of a subset may have
+    budget: usize,
 }
 
-fn enum_subsets_impl(counts: &mut [u8; 26], mut index: usize) -> Vec<AlphaMultiset> {
-    while index < counts.len() && counts[index] == 0 {
-        index += 1;
+impl<'a> Subsets<'a> {
+    /// Whether a subset may have `count` of `letter`, and the blanks that would use up if so
+    fn blanks_for(&self, letter: usize, count: usize, frame: &SubsetFrame) -> Option<usize> {
+        let missing = count.saturating_sub(self.counts[letter] as usize);
+
+        if count < self.needed[letter] as usize
+            || (count > 0 && self.banned[letter] > 0)
+            || count > frame.budget
+            || missing > frame.blanks
+        {
+            None
+        } else {
+            Some(missing)
+        }
     }
-    if index >= counts.len() {
-        return vec![AlphaMultiset::from(&*counts)];
+
+    /// Index of the first set of the range of `frame` up to `end` with more than `most` of the
+    /// letter of `frame`
+    fn first_with_more(&self, frame: &SubsetFrame, end: usize, most: usize) -> usize {
+        let sets = &self.sets[frame.start..end];
+        let first = sets.binary_search_by(|s| match count(s, frame.letter) <= most {
+            true => Ordering::Less,
+            false => Ordering::Greater,
+        });
+
+        return frame.start + first.unwrap_err();
     }
 
-    let mut subsets = Vec::new();
-    let ocount = counts[index];
+    /// Whether the only set left in the range of `frame` is a subset, checking the rest of its
+    /// letters at once
+    fn fits(&self, frame: &SubsetFrame) -> bool {
+        let set = &self.sets[frame.start];
+        let mut frame = *frame;
 
-    for c in 0..=ocount {
-        counts[index] = c;
-        subsets.extend(enum_subsets_impl(counts, index + 1));
+        if set.len() < self.min_len {
+            return false;
+        }
+
+        for letter in frame.letter..self.letters {
+            let c = count(set, letter);
+            match self.blanks_for(letter, c, &frame) {
+                Some(missing) => {
+                    frame.blanks -= missing;
+                    frame.budget -= c;
+                }
+                None => return false,
+            }
+        }
+
+        return true;
     }
+}
+
+impl<'a> Iterator for Subsets<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.pop()?;
+            self.steps += 1;
+            // not even all of the looked up letters left and the blanks make a long enough subset
+            if frame.len + self.rest[frame.letter] + frame.blanks < self.min_len {
+                continue;
+            }
+            if frame.end - frame.start == 1 {
+                if self.fits(&frame) {
+                    return Some(frame.start);
+                }
+                continue;
+            }
+            // every set of the range has the same counts by now, so there's only one
+            if frame.letter >= self.letters {
+                if frame.len >= self.min_len {
+                    return Some(frame.start);
+                }
+                continue;
+            }
+
+            // the sets of the range are in order of their count of this letter, so go through the
+            // sets with each count from the highest allowed, to make up subsets with the lowest
+            // counts first
+            let letter = frame.letter;
+            let most = match self.banned[letter] {
+                0 => (self.counts[letter] as usize + frame.blanks).min(frame.budget),
+                _ => 0,
+            };
+            let mut end = self.first_with_more(&frame, frame.end, most);
+            while end > frame.start {
+                let c = count(&self.sets[end - 1], letter);
+                if c < self.needed[letter] as usize {
+                    break;
+                }
+                let start = match c {
+                    0 => frame.start,
+                    _ => self.first_with_more(&frame, end, c - 1),
+                };
 
-    return subsets;
+                let missing = c.saturating_sub(self.counts[letter] as usize);
+                self.stack.push(SubsetFrame {
+                    letter: letter + 1,
+                    start,
+                    end,
+                    blanks: frame.blanks - missing,
+                    len: frame.len + c,
+                    budget: frame.budget - c,
+                });
+                end = start;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_simple_enum() {
-        let mut subs = enum_subsets(AlphaMultiset::from("abc"))
-            .into_iter()
-            .map(|s| s.to_string())
+    /// An index of every set in `sets`, along with some others which aren't subsets of the letters
+    /// looked up below
+    fn index(sets: &[&str]) -> ExpSearcher {
+        let wordmap = sets
+            .iter()
+            .chain(&["d", "abd", "bcc", "aaaa", "aacc", "zzz"])
+            .map(|&s| {
+                (
+                    AlphaMultiset::from(s),
+                    vec![Word::from_pair(s.to_string(), 1)],
+                )
+            })
+            .collect();
+
+        ExpSearcher::from_wordmap(wordmap, Alphabet::default())
+    }
+
+    fn subsets(searcher: &ExpSearcher, letters: &str, bounds: &FilterBounds) -> Vec<String> {
+        let mut subs = searcher
+            .subsets(AlphaMultiset::from(letters), bounds)
+            .map(|i| searcher.sets[i].to_string())
             .collect::<Vec<_>>();
         subs.sort();
+        subs
+    }
+
+    #[test]
+    fn test_simple_enum() {
+        let searcher = index(&["", "a", "ab", "abc", "ac", "b", "bc", "c"]);
+        let subs = subsets(&searcher, "abc", &FilterBounds::default());
 
         assert_eq!(subs, vec!["", "a", "ab", "abc", "ac", "b", "bc", "c"]);
     }
 
     #[test]
     fn test_simple_enum2() {
-        let mut subs = enum_subsets(AlphaMultiset::from("aaac"))
-            .into_iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
-        subs.sort();
+        let searcher = index(&["", "a", "aa", "aaa", "aaac", "aac", "ac", "c"]);
+        let subs = subsets(&searcher, "aaac", &FilterBounds::default());
 
         assert_eq!(subs, vec!["", "a", "aa", "aaa", "aaac", "aac", "ac", "c"]);
     }
+
+    #[test]
+    fn test_blank_enum() {
+        let letters: Vec<_> = ('a'..='z').map(String::from).collect();
+        let pairs: Vec<_> = ('a'..='z')
+            .flat_map(|a| (a..='z').map(move |b| format!("{}{}", a, b)))
+            .collect();
+        let mut sets = vec![""];
+        sets.extend(letters.iter().map(String::as_str));
+        sets.extend(pairs.iter().map(String::as_str));
+        let searcher = index(&sets);
+
+        let subs = subsets(&searcher, "a?", &FilterBounds::default());
+
+        // the empty set, any single letter, or an "a" with any other letter
+        assert_eq!(subs.len(), 1 + 26 + 26);
+        assert!(subs.contains(&"aa".to_string()));
+        assert!(subs.contains(&"az".to_string()));
+        assert!(subs.contains(&"q".to_string()));
+    }
+
+    #[test]
+    fn test_bounded_enum() {
+        let searcher = index(&["", "a", "ab", "abc", "ac", "b", "bc", "c"]);
+        let bounds = FilterBounds {
+            min_len: 2,
+            max_len: 2,
+            required: AlphaMultiset::from("b"),
+            banned: AlphaMultiset::from("c"),
+        };
+        let subs = subsets(&searcher, "abc", &bounds);
+
+        assert_eq!(subs, vec!["ab"]);
+
+        // only the blank can make up for the letters `min_len` needs beyond the looked up ones
+        let bounds = FilterBounds {
+            min_len: 3,
+            ..FilterBounds::default()
+        };
+        assert_eq!(subsets(&searcher, "ab?", &bounds), vec!["abc", "abd"]);
+    }
 }
@@ -0,0 +1,222 @@
+use super::set_automaton::{SetAutomaton, SetAutomatonIter};
+use super::{
+    try_wordlist_to_wordmap, wordlist_to_wordmap, AlphaMultiset, FilterBounds, WordSearcher,
+};
+use crate::{Alphabet, Filter, LookupOptions, LookupStats, Result, Word, WordRef, Wordlist};
+use fst::raw::{CompiledAddr, Output};
+use fst::Map;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The sorted letters of every set in a finite state transducer, whose output for a set is the
+/// index of its words in the word table
+///
+/// Each letter is stored as a single byte counting up from `a`, so for English the keys are just
+/// what `AlphaMultiset`'s `Display` writes, like `aabce`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FstSearcher {
+    #[serde(with = "map_bytes")]
+    map: Map<Vec<u8>>,
+    /// words of every set, in the order of their keys
+    words: Vec<Vec<Word>>,
+    alphabet: Alphabet,
+}
+
+impl std::fmt::Debug for FstSearcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FstSearcher")
+            .field("keys", &self.map.len())
+            .field("fst_bytes", &self.map.as_fst().size())
+            .field("alphabet", &self.alphabet)
+            .finish()
+    }
+}
+
+impl Default for FstSearcher {
+    fn default() -> Self {
+        Self::from_embedded_wordlist()
+    }
+}
+
+impl FstSearcher {
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist.into())?;
+        Ok(Self::from_wordmap(wordmap, alphabet))
+    }
+    pub fn from_embedded_wordlist() -> Self {
+        let (wordmap, alphabet) = wordlist_to_wordmap(Wordlist::embedded());
+        Self::from_wordmap(wordmap, alphabet)
+    }
+
+    fn from_wordmap(wordmap: HashMap<AlphaMultiset, Vec<Word>>, alphabet: Alphabet) -> Self {
+        let mut entries: Vec<_> = wordmap
+            .into_iter()
+            .map(|(set, words)| (set_key(&set), words))
+            .collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let map = Map::from_iter(
+            entries
+                .iter()
+                .enumerate()
+                .map(|(i, (key, _))| (key, i as u64)),
+        )
+        .expect("keys of distinct sets are sorted and distinct");
+
+        Self {
+            map,
+            words: entries.into_iter().map(|(_, words)| words).collect(),
+            alphabet,
+        }
+    }
+
+    /// Serialize the searcher, e.g. to be embedded in the binary
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("failed to serialize FstSearcher")
+    }
+
+    /// Use a searcher serialized with `to_bytes`, reporting one which can't be decoded
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    fn lookup_iter_bounded(&self, word: &str, prune: FilterBounds) -> SetAutomatonIter<'_, Self> {
+        SetAutomatonIter::new(self, &self.alphabet.to_set(word), prune)
+    }
+}
+
+impl SetAutomaton for FstSearcher {
+    /// a node, and the output of the transitions on the way to it
+    type State = (CompiledAddr, Output);
+
+    fn root(&self) -> Self::State {
+        (self.map.as_fst().root().addr(), Output::zero())
+    }
+
+    fn visit(
+        &self,
+        (addr, output): Self::State,
+        transitions: &mut Vec<(usize, Self::State)>,
+    ) -> Option<&[Word]> {
+        let node = self.map.as_fst().node(addr);
+
+        // bytes below `a` wrap around to letters past any alphabet, which are skipped
+        transitions.extend(node.transitions().map(|t| {
+            let letter = t.inp.wrapping_sub(b'a') as usize;
+            (letter, (t.addr, output.cat(t.out)))
+        }));
+
+        if node.is_final() {
+            let i = output.cat(node.final_output()).value() as usize;
+            Some(&self.words[i])
+        } else {
+            None
+        }
+    }
+}
+
+impl WordSearcher for FstSearcher {
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        Box::new(self.lookup_iter_bounded(word, FilterBounds::default()))
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the states visited
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut iter = self.lookup_iter_bounded(word, FilterBounds::default());
+            let words = iter.by_ref().map(Word::from).collect();
+            (words, iter.steps)
+        })
+    }
+
+    /// Doesn't follow transitions which are too long, use banned letters or skip required ones
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        Box::new(
+            self.lookup_iter_bounded(word, bounds)
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+}
+
+/// The key of `set` in the transducer: its letters in order, each repeated as many times as the
+/// set has it
+fn set_key(set: &AlphaMultiset) -> Vec<u8> {
+    let mut key = Vec::with_capacity(set.len());
+    for (letter, &count) in set.char_counts().iter().enumerate() {
+        key.extend(std::iter::repeat(b'a' + letter as u8).take(count as usize));
+    }
+
+    return key;
+}
+
+/// Serializes the transducer as the bytes it's laid out in
+mod map_bytes {
+    use fst::Map;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(map: &Map<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(map.as_fst().as_bytes())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Map<Vec<u8>>, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Map::new(bytes).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let bytes = b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\ndab 1\n";
+        let searcher = FstSearcher::from_wordlist(Wordlist::from_bytes(bytes));
+
+        // a round trip through its bytes keeps the transducer intact
+        let round_trip = FstSearcher::try_from_bytes(&searcher.to_bytes()).unwrap();
+        assert_eq!(round_trip.to_bytes(), searcher.to_bytes());
+        assert_eq!(round_trip.lookup("facedb?"), searcher.lookup("facedb?"));
+        assert!(FstSearcher::try_from_bytes(b"not a searcher").is_err());
+    }
+
+    #[test]
+    fn test_smaller_than_dag_index() {
+        // the most frequent words of the embedded wordlist, which are enough for the shared suffixes
+        // of the fst to pay off
+        let lines = super::super::EMBEDDED_WORDLIST
+            .split(|&b| b == b'\n')
+            .take(5000)
+            .collect::<Vec<_>>()
+            .join(&b'\n');
+        let wordlist = || Wordlist::from_bytes(&lines);
+
+        let fst = FstSearcher::from_wordlist(wordlist()).to_bytes().len();
+        let dag = crate::DAGSearcher::from_wordlist(wordlist())
+            .to_index_bytes()
+            .len();
+        assert!(
+            fst < dag,
+            "fst searcher: {} bytes, dag index: {} bytes",
+            fst,
+            dag
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use super::{DAGIndex, DAGSearcher, ExpSearcher, SimpleSearcher, WordSearcher};
+use crate::{
+    Alphabet, Error, Filter, LookupOptions, LookupStats, Ranking, Result, Word, WordRef, Wordlist,
+};
+
+/// Most letters (including blanks) looked up by `calibrate`, whose choice is used for any longer
+/// lookups too
+#[cfg(not(target_arch = "wasm32"))]
+const CALIBRATION_LEN: usize = 20;
+/// Lookups of each number of letters timed by `calibrate`
+#[cfg(not(target_arch = "wasm32"))]
+const CALIBRATION_QUERIES: usize = 3;
+
+/// Several lookup indices of the same words, each of which is used for the lookups of the numbers of
+/// letters it's the fastest at
+///
+/// Which one that is depends on the wordlist and the machine, so the thresholds can be measured
+/// with `calibrate`.
+pub struct HybridSearcher {
+    /// searchers to choose from, along with their names
+    searchers: Vec<(String, Box<dyn WordSearcher>)>,
+    /// index of the searcher to look up each number of letters with, the last one for any more
+    /// letters too
+    choices: Vec<usize>,
+}
+
+impl Default for HybridSearcher {
+    fn default() -> Self {
+        Self::from_embedded_wordlist()
+    }
+}
+
+impl HybridSearcher {
+    /// Look up all words with `searcher`, until searchers for longer lookups are added
+    pub fn new<S: WordSearcher + 'static>(name: &str, searcher: S) -> Self {
+        Self {
+            searchers: vec![(name.to_string(), Box::new(searcher))],
+            choices: vec![0],
+        }
+    }
+
+    /// Look up words of `min_len` letters or more with `searcher`, which has to be an index of the
+    /// same words in the same alphabet
+    ///
+    /// A searcher with another alphabet is reported instead, as it couldn't look up the same letters.
+    pub fn with_searcher<S: WordSearcher + 'static>(
+        mut self,
+        name: &str,
+        searcher: S,
+        min_len: usize,
+    ) -> Result<Self> {
+        if searcher.alphabet() != self.alphabet() {
+            return Err(Error::AlphabetMismatch(name.to_string()));
+        }
+        self.searchers.push((name.to_string(), Box::new(searcher)));
+
+        let last = *self.choices.last().unwrap();
+        if self.choices.len() <= min_len {
+            self.choices.resize(min_len + 1, last);
+        }
+        for choice in &mut self.choices[min_len..] {
+            *choice = self.searchers.len() - 1;
+        }
+
+        Ok(self)
+    }
+
+    /// An `ExpSearcher` for short lookups, a `DAGSearcher` for longer ones and a `SimpleSearcher`
+    /// for the longest, until calibrated
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let wordlist = wordlist.into();
+
+        Self::new("exp", ExpSearcher::try_from_wordlist(wordlist.clone())?)
+            .with_searcher("dag", DAGSearcher::try_from_wordlist(wordlist.clone())?, 7)?
+            .with_searcher("simple", SimpleSearcher::try_from_wordlist(wordlist)?, 13)
+    }
+
+    /// Like `from_wordlist` for the embedded wordlist, searching the embedded "dag.bin" in place
+    pub fn from_embedded_wordlist() -> Self {
+        Self::new("exp", ExpSearcher::from_embedded_wordlist())
+            .with_searcher("dag", DAGIndex::embedded(), 7)
+            .and_then(|this| {
+                this.with_searcher("simple", SimpleSearcher::from_embedded_wordlist(), 13)
+            })
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Name of the searcher which looks up `word`
+    pub fn chosen(&self, word: &str) -> &str {
+        &self.searchers[self.choice(word)].0
+    }
+
+    /// Time every searcher looking up a few letters of every length, and use the fastest one for
+    /// each length from then on
+    ///
+    /// This needs a clock, which WASM builds don't have, so the thresholds have to be given to
+    /// `with_searcher` there instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn calibrate(&mut self) {
+        let queries = calibration_queries(self.alphabet(), CALIBRATION_LEN, CALIBRATION_QUERIES);
+        let queries: Vec<_> = queries.iter().map(String::as_str).collect();
+
+        self.calibrate_with(&queries);
+    }
+
+    /// Like `calibrate`, but timing lookups of `queries`, e.g. ones collected from actual players
+    ///
+    /// Numbers of letters without any queries are looked up like the next fewer letters.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn calibrate_with(&mut self, queries: &[&str]) {
+        let mut times: Vec<Vec<std::time::Duration>> = Vec::new();
+
+        for query in queries {
+            let len = self.query_len(query);
+            if times.len() <= len {
+                times.resize(len + 1, Vec::new());
+            }
+            if times[len].is_empty() {
+                times[len] = vec![Default::default(); self.searchers.len()];
+            }
+
+            for (time, (_, searcher)) in times[len].iter_mut().zip(self.searchers.iter()) {
+                let start = std::time::Instant::now();
+                searcher.lookup(query);
+                *time += start.elapsed();
+            }
+        }
+
+        if let Some(choices) = fastest(&times) {
+            self.choices = choices;
+        }
+    }
+
+    fn choice(&self, word: &str) -> usize {
+        let len = self.query_len(word);
+        self.choices[len.min(self.choices.len() - 1)]
+    }
+
+    /// Number of letters of `word`, including blanks, leaving letters which can't be looked up to
+    /// be reported by the chosen searcher
+    fn query_len(&self, word: &str) -> usize {
+        self.alphabet().try_to_set(word).map_or(0, |set| set.len())
+    }
+
+    fn searcher(&self, word: &str) -> &dyn WordSearcher {
+        &*self.searchers[self.choice(word)].1
+    }
+}
+
+impl WordSearcher for HybridSearcher {
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        self.searcher(word).lookup_iter(word)
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        self.searchers[0].1.alphabet()
+    }
+
+    fn lookup(&self, word: &str) -> Vec<Word> {
+        self.searcher(word).lookup(word)
+    }
+
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        self.searcher(word).lookup_stats(word)
+    }
+
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        self.searcher(word).lookup_iter_with(word, filter, options)
+    }
+
+    fn lookup_top_k(&self, word: &str, k: usize, ranking: Ranking) -> Vec<Word> {
+        self.searcher(word).lookup_top_k(word, k, ranking)
+    }
+}
+
+/// The fastest searcher for each number of letters, given the `times[len][searcher]` it took them
+/// to look up that many letters, with lengths which weren't timed going to the next shorter length
+/// that was, or the shortest one if there is none
+#[cfg(not(target_arch = "wasm32"))]
+fn fastest<T: Ord + Copy>(times: &[Vec<T>]) -> Option<Vec<usize>> {
+    let fastest: Vec<_> = times
+        .iter()
+        .map(|times| (0..times.len()).min_by_key(|&i| times[i]))
+        .collect();
+    let mut last = fastest.iter().find_map(|&choice| choice)?;
+
+    let choices = fastest
+        .into_iter()
+        .map(|choice| {
+            last = choice.unwrap_or(last);
+            last
+        })
+        .collect();
+
+    return Some(choices);
+}
+
+/// `count` lookups of each number of letters from 1 to `max_len`, of letters of `alphabet` picked
+/// the same way every time
+#[cfg(not(target_arch = "wasm32"))]
+fn calibration_queries(alphabet: &Alphabet, max_len: usize, count: usize) -> Vec<String> {
+    let letters = alphabet.letters();
+    // xorshift, which is plenty random for picking letters
+    let mut state: u32 = 0x2545_f491;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as usize
+    };
+
+    let mut queries = Vec::with_capacity(max_len * count);
+    for len in 1..=max_len {
+        for _ in 0..count {
+            queries.push((0..len).map(|_| letters[next() % letters.len()]).collect());
+        }
+    }
+
+    return queries;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch() {
+        let bytes = b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\ndab 1\n";
+        let wordlist = || Wordlist::from_bytes(bytes);
+        let mut hybrid = HybridSearcher::from_wordlist(wordlist());
+
+        assert_eq!(hybrid.chosen("abc"), "exp");
+        assert_eq!(hybrid.chosen("abcde??"), "dag");
+        assert_eq!(hybrid.chosen("abcdefghijklmnopqrstuvwxyz"), "simple");
+
+        // lookups of more letters than were calibrated are made like the most calibrated letters
+        hybrid.calibrate();
+        let longest = "?".repeat(CALIBRATION_LEN);
+        assert_eq!(
+            hybrid.chosen(&longest),
+            hybrid.chosen(&"?".repeat(CALIBRATION_LEN + 5))
+        );
+        assert!(hybrid.try_lookup("abé").is_err());
+
+        let spanish = SimpleSearcher::from_wordlist(wordlist().with_alphabet(Alphabet::spanish()));
+        assert!(matches!(
+            hybrid.with_searcher("spanish", spanish, 5),
+            Err(Error::AlphabetMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_fastest() {
+        assert_eq!(fastest::<u32>(&[]), None);
+        assert_eq!(fastest::<u32>(&[vec![], vec![]]), None);
+        assert_eq!(
+            fastest(&[vec![], vec![3, 1, 2], vec![], vec![2, 3, 1], vec![]]),
+            Some(vec![1, 1, 1, 2, 2])
+        );
+    }
+}
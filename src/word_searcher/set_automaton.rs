@@ -0,0 +1,134 @@
+use super::{AlphaMultiset, FilterBounds, MAX_LETTERS};
+use crate::{Word, WordRef};
+
+/// An acyclic automaton accepting the letters of every set of a word table in order, like `aabce`,
+/// which `SetAutomatonIter` searches through
+pub(super) trait SetAutomaton {
+    /// A state of the automaton, along with whatever it accumulates on the way to it
+    type State: Copy;
+
+    fn root(&self) -> Self::State;
+
+    /// Add the transitions out of `state` to `transitions` as `(letter, target)` pairs in order of
+    /// the letters, returning the words of the set read on the way if `state` is final
+    fn visit(
+        &self,
+        state: Self::State,
+        transitions: &mut Vec<(usize, Self::State)>,
+    ) -> Option<&[Word]>;
+}
+
+/// Depth first search through a `SetAutomaton`, keeping track of the letters that are still
+/// available
+pub(super) struct SetAutomatonIter<'a, A: SetAutomaton> {
+    automaton: &'a A,
+    /// states left to visit
+    stack: Vec<Frame<A::State>>,
+    /// the most letters a word may have
+    max_len: usize,
+    /// letters a word may not have
+    banned: [u8; MAX_LETTERS],
+    /// words of the last final state visited, which are yet to be returned
+    words: std::slice::Iter<'a, Word>,
+    /// transitions out of the last state visited, kept to reuse their allocation
+    transitions: Vec<(usize, A::State)>,
+    /// states visited so far
+    pub steps: usize,
+}
+
+/// A state left to visit, along with the state of the search on the way to it
+struct Frame<S> {
+    state: S,
+    /// letters not yet used up
+    counts: [u8; MAX_LETTERS],
+    blanks: usize,
+    /// letters which the word must still contain
+    needed: [u8; MAX_LETTERS],
+    needed_total: usize,
+    /// letters read on the way to the state
+    depth: usize,
+}
+
+impl<'a, A: SetAutomaton> SetAutomatonIter<'a, A> {
+    /// Search for the sets which can be made from `set`, skipping those `prune` rules out
+    pub fn new(automaton: &'a A, set: &AlphaMultiset, prune: FilterBounds) -> Self {
+        let needed = prune.required.char_counts();
+        let stack = if prune.is_satisfiable(set) {
+            vec![Frame {
+                state: automaton.root(),
+                counts: set.char_counts(),
+                blanks: set.blanks(),
+                needed,
+                needed_total: needed.iter().map(|&c| c as usize).sum(),
+                depth: 0,
+            }]
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            automaton,
+            stack,
+            max_len: prune.max_len,
+            banned: prune.banned.char_counts(),
+            words: [].iter(),
+            transitions: Vec::new(),
+            steps: 0,
+        }
+    }
+}
+
+impl<'a, A: SetAutomaton> Iterator for SetAutomatonIter<'a, A> {
+    type Item = WordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.words.next() {
+                return Some(word.into());
+            }
+
+            let frame = self.stack.pop()?;
+            self.steps += 1;
+            self.transitions.clear();
+            let automaton = self.automaton;
+            if let Some(words) = automaton.visit(frame.state, &mut self.transitions) {
+                if frame.needed_total == 0 {
+                    self.words = words.iter();
+                }
+            }
+            if frame.depth + frame.needed_total.max(1) > self.max_len {
+                continue;
+            }
+
+            // in reverse, so the sets are visited in order
+            for &(letter, state) in self.transitions.iter().rev() {
+                if letter >= MAX_LETTERS || self.banned[letter] > 0 {
+                    continue;
+                }
+                // letters only go up along a set, so a needed letter can't be read later on
+                if frame.needed_total > 0 && frame.needed[..letter].iter().any(|&n| n > 0) {
+                    continue;
+                }
+
+                let mut next = Frame {
+                    state,
+                    depth: frame.depth + 1,
+                    ..frame
+                };
+                if next.counts[letter] > 0 {
+                    next.counts[letter] -= 1;
+                } else if next.blanks > 0 {
+                    next.blanks -= 1;
+                } else {
+                    continue;
+                }
+                if next.needed[letter] > 0 {
+                    next.needed[letter] -= 1;
+                    next.needed_total -= 1;
+                }
+
+                self.stack.push(next);
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use super::{
-    embedded_wordlist_iter, iter_to_wordmap, path_to_iter, str_to_set, AlphaMultiset, WordSearcher,
+    try_wordlist_to_wordmap, wordlist_to_wordmap, AlphaMultiset, FilterBounds, WordSearcher,
 };
-use crate::Word;
+use crate::{Alphabet, Filter, LookupOptions, LookupStats, Result, Word, WordRef, Wordlist};
 use serde::{Deserialize, Serialize};
 use fnv::FnvHashMap;
 use std::collections::HashMap;
@@ -11,6 +11,7 @@ pub struct SimpleSearcher {
     length_inds: FnvHashMap<usize, usize>,
     sorted_ind_keys: Vec<usize>,
     words: Vec<(AlphaMultiset, Vec<Word>)>,
+    alphabet: Alphabet,
 }
 
 impl Default for SimpleSearcher {
@@ -20,20 +21,27 @@ impl Default for SimpleSearcher {
 }
 
 impl SimpleSearcher {
-    pub fn from_wordlist<P: AsRef<std::path::Path>>(path: P) -> Self {
-        Self::from_wordmap(iter_to_wordmap(path_to_iter(path)))
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist.into())?;
+        Ok(Self::from_wordmap(wordmap, alphabet))
     }
 
     pub fn from_embedded_wordlist() -> Self {
-        Self::from_wordmap(iter_to_wordmap(embedded_wordlist_iter()))
+        let (wordmap, alphabet) = wordlist_to_wordmap(Wordlist::embedded());
+        Self::from_wordmap(wordmap, alphabet)
     }
 
-    fn from_wordmap(map: HashMap<AlphaMultiset, Vec<Word>>) -> Self {
+    fn from_wordmap(map: HashMap<AlphaMultiset, Vec<Word>>, alphabet: Alphabet) -> Self {
         let mut words: Vec<_> = map.into_iter().collect();
         words.sort_unstable_by_key(|(s, _)| -(s.len() as isize));
         
-        let min_length = words.last().unwrap().0.len();
-        let max_length = words.first().unwrap().0.len();
+        // an empty table has a single empty length, so lookups find nothing instead of panicking
+        let min_length = words.last().map_or(0, |(set, _)| set.len());
+        let max_length = words.first().map_or(0, |(set, _)| set.len());
         let mut cur_length = min_length;
         let mut length_inds = FnvHashMap::default();
 
@@ -54,9 +62,61 @@ impl SimpleSearcher {
             length_inds,
             words,
             sorted_ind_keys,
+            alphabet,
         }
     }
 
+    /// Look up the words which can be made from each of `words` in one pass over the word table,
+    /// like precomputing the answers of a whole level pack
+    ///
+    /// With the `parallel` feature, parts of the table are scanned on all cores at once. Words are
+    /// found in the same order as by `lookup` either way.
+    pub fn lookup_batch(&self, words: &[&str]) -> Vec<Vec<Word>> {
+        self.try_lookup_batch(words)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `lookup_batch`, but reports letters which can't be looked up instead of panicking
+    pub fn try_lookup_batch(&self, words: &[&str]) -> Result<Vec<Vec<Word>>> {
+        let letter_sets = words
+            .iter()
+            .map(|w| self.alphabet.try_to_set(w))
+            .collect::<Result<Vec<_>>>()?;
+        let longest = letter_sets
+            .iter()
+            .map(AlphaMultiset::len)
+            .max()
+            .unwrap_or(0);
+        let table = &self.words[self.start_index(longest)..];
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            Ok(table
+                .par_chunks(BATCH_CHUNK_SIZE)
+                .map(|chunk| scan_batch(chunk, &letter_sets))
+                .reduce(
+                    || vec![Vec::new(); letter_sets.len()],
+                    |mut found, more| {
+                        for (found, more) in found.iter_mut().zip(more) {
+                            found.extend(more);
+                        }
+                        found
+                    },
+                ))
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            Ok(scan_batch(table, &letter_sets))
+        }
+    }
+
+    /// Index of the first set in `words` of at most `len` letters
+    fn start_index(&self, len: usize) -> usize {
+        self.length_inds[&self.find_closest_index_key(len)]
+    }
+
     fn find_closest_index_key(&self, n: usize) -> usize {
         match self.sorted_ind_keys.binary_search(&n) {
             Ok(_) => n,
@@ -65,22 +125,143 @@ impl SimpleSearcher {
             }
         }
     }
+
+    /// The range of `words` holding sets of `min` up to `max` letters
+    fn length_range(&self, min: usize, max: usize) -> std::ops::Range<usize> {
+        let longest = self.sorted_ind_keys.iter().rev().find(|&&l| l <= max);
+
+        match longest {
+            Some(l) if *l >= min => {
+                // the next shorter length starts right after the last set of length `min`
+                let end = self
+                    .sorted_ind_keys
+                    .iter()
+                    .rev()
+                    .find(|&&l| l < min)
+                    .map_or(self.words.len(), |l| self.length_inds[l]);
+                self.length_inds[l]..end
+            }
+            _ => 0..0,
+        }
+    }
 }
 
 impl WordSearcher for SimpleSearcher {
     /// Do a linear lookup over dictionary words with length <= the given word
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let letter_set = self.alphabet.to_set(word);
+        let start_ind = self.start_index(letter_set.len());
+
+        Box::new(
+            self.words[start_ind..]
+                .iter()
+                .filter(move |(set, _)| letter_set.has_subset(set))
+                .flat_map(|(_, strs)| strs.iter().map(WordRef::from)),
+        )
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the sets checked, which are all the sets of at most as many letters
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let letters = self.alphabet.to_set(word).len();
+            let scanned = self.words.len() - self.start_index(letters);
+            (self.lookup(word), scanned)
+        })
+    }
+
+    /// Scans the word table on all cores, finding words in the same order as `lookup_iter`
+    #[cfg(feature = "parallel")]
     fn lookup(&self, word: &str) -> Vec<Word> {
-        let index_len = self.find_closest_index_key(word.len());
-        let start_ind = *self.length_inds.get(&index_len).unwrap();
-        let letter_set = str_to_set(word);
-        let mut words = Vec::new();
-
-        for (set, strs) in &self.words[start_ind..] {
-            if letter_set.has_subset(&set) {
-                words.extend_from_slice(&*strs);
+        use rayon::prelude::*;
+
+        let letter_set = self.alphabet.to_set(word);
+        let sets: Vec<_> = self.words[self.start_index(letter_set.len())..]
+            .par_iter()
+            .filter(|(set, _)| letter_set.has_subset(set))
+            .collect();
+
+        sets.into_iter()
+            .flat_map(|(_, strs)| strs.iter().cloned())
+            .collect()
+    }
+
+    /// Only scans the words of the lengths the filter allows, skipping sets with the wrong letters
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let letter_set = self.alphabet.to_set(word);
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        if !bounds.is_satisfiable(&letter_set) {
+            return Box::new(std::iter::empty());
+        }
+
+        let range = self.length_range(bounds.min_len, bounds.max_len.min(letter_set.len()));
+
+        Box::new(
+            self.words[range]
+                .iter()
+                .filter(move |(set, _)| letter_set.has_subset(set) && bounds.admits(set))
+                .flat_map(|(_, strs)| strs.iter().map(WordRef::from))
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+}
+
+/// Sets of the word table each thread of a parallel batch lookup scans at a time
+#[cfg(feature = "parallel")]
+const BATCH_CHUNK_SIZE: usize = 1024;
+
+/// The words of `table` which can be made from each of `letter_sets`
+fn scan_batch(
+    table: &[(AlphaMultiset, Vec<Word>)],
+    letter_sets: &[AlphaMultiset],
+) -> Vec<Vec<Word>> {
+    let mut found = vec![Vec::new(); letter_sets.len()];
+
+    for (set, strs) in table {
+        for (letters, found) in letter_sets.iter().zip(found.iter_mut()) {
+            if letters.has_subset(set) {
+                found.extend(strs.iter().cloned());
             }
         }
+    }
+
+    return found;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_batch() {
+        let searcher = SimpleSearcher::from_wordlist(Wordlist::from_bytes(
+            b"ace 9\ncafe 8\nface 7\nfaced 6\ndeaf 5\nfade 4\nbead 3\nbad 2\ndab 1\n",
+        ));
+        let batch = ["facedb", "ab?", "", "zzz", "face"];
+
+        let found = searcher.lookup_batch(&batch);
+        assert_eq!(found.len(), batch.len());
+        for (letters, found) in batch.iter().zip(found) {
+            assert_eq!(found, searcher.lookup(letters));
+            assert_eq!(
+                found,
+                searcher
+                    .lookup_iter(letters)
+                    .map(Word::from)
+                    .collect::<Vec<_>>()
+            );
+        }
 
-        return words;
+        assert!(searcher.try_lookup_batch(&["abc", "abé"]).is_err());
     }
 }
@@ -1,15 +1,16 @@
 use super::{
-    embedded_wordlist_iter, iter_to_wordmap, path_to_iter, str_to_set, AlphaMultiset, BitArray,
-    WordSearcher,
+    try_edited_word, try_wordlist_to_wordmap, wordlist_to_wordmap, AlphaMultiset,
+    EditableWordSearcher, FilterBounds, WordSearcher, MAX_LETTERS,
 };
-use crate::Word;
+use crate::{Alphabet, Filter, LookupOptions, LookupStats, Result, Word, WordRef, Wordlist};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// A binary trie based on the bitset representation of a word
+/// A trie over the letter counts of each word, branching on the count of one letter at a time
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrieSearcher {
     trie_root: TrieNode,
+    alphabet: Alphabet,
 }
 
 impl Default for TrieSearcher {
@@ -19,81 +20,365 @@ impl Default for TrieSearcher {
 }
 
 impl TrieSearcher {
-    pub fn from_wordlist<P: AsRef<std::path::Path>>(path: P) -> Self {
-        Self::from_wordmap(iter_to_wordmap(path_to_iter(path)))
+    pub fn from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Self {
+        Self::try_from_wordlist(wordlist).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_wordlist<'a, W: Into<Wordlist<'a>>>(wordlist: W) -> Result<Self> {
+        let (wordmap, alphabet) = try_wordlist_to_wordmap(wordlist.into())?;
+        Ok(Self::from_wordmap(wordmap, alphabet))
     }
 
     pub fn from_embedded_wordlist() -> Self {
-        Self::from_wordmap(iter_to_wordmap(embedded_wordlist_iter()))
+        let (wordmap, alphabet) = wordlist_to_wordmap(Wordlist::embedded());
+        Self::from_wordmap(wordmap, alphabet)
     }
 
-    fn from_wordmap(wordmap: HashMap<AlphaMultiset, Vec<Word>>) -> Self {
+    fn from_wordmap(wordmap: HashMap<AlphaMultiset, Vec<Word>>, alphabet: Alphabet) -> Self {
         let mut trie_root = TrieNode::default();
 
         for (set, words) in wordmap {
             trie_root.insert((set, words));
         }
 
-        Self { trie_root }
+        Self {
+            trie_root,
+            alphabet,
+        }
     }
 }
 
 impl WordSearcher for TrieSearcher {
-    fn lookup(&self, word: &str) -> Vec<Word> {
-        self.trie_root.lookup(str_to_set(word))
+    fn lookup_iter<'a>(&'a self, word: &str) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        Box::new(
+            self.trie_root
+                .lookup_iter(self.alphabet.to_set(word), FilterBounds::default()),
+        )
+    }
+
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Counts the trie nodes visited
+    fn lookup_stats(&self, word: &str) -> (Vec<Word>, LookupStats) {
+        LookupStats::measure(|| {
+            let mut iter = self
+                .trie_root
+                .lookup_iter(self.alphabet.to_set(word), FilterBounds::default());
+            let words = iter.by_ref().map(Word::from).collect();
+            (words, iter.steps)
+        })
+    }
+
+    /// Prunes branches which are too long, use banned letters or skip required ones
+    fn lookup_iter_with<'a>(
+        &'a self,
+        word: &str,
+        filter: &Filter,
+        options: &LookupOptions,
+    ) -> Box<dyn Iterator<Item = WordRef<'a>> + 'a> {
+        let bounds = FilterBounds::new(filter, options, &self.alphabet);
+        let filter = filter.clone();
+
+        Box::new(
+            self.trie_root
+                .lookup_iter(self.alphabet.to_set(word), bounds)
+                .filter(move |w| filter.matches_in(w, &self.alphabet)),
+        )
+    }
+}
+
+impl EditableWordSearcher for TrieSearcher {
+    fn try_insert(&mut self, word: &str, frequency: usize) -> Result<()> {
+        let (word, set) = match try_edited_word(word, &self.alphabet)? {
+            Some(edited) => edited,
+            None => return Ok(()),
+        };
+
+        let words = &mut self.trie_root.entry(0, &set.char_counts()).words;
+        match words.iter_mut().find(|w| ***w == *word) {
+            Some(w) => *w = Word::from_pair(word, frequency),
+            None => words.push(Word::from_pair(word, frequency)),
+        }
+
+        Ok(())
+    }
+
+    /// Also removes the nodes which are left without any words below them
+    fn remove(&mut self, word: &str) -> bool {
+        match try_edited_word(word, &self.alphabet) {
+            Ok(Some((word, set))) => self.trie_root.remove(0, &set.char_counts(), &word),
+            _ => false,
+        }
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// A node of a trie over the letter counts of a multiset, which branches on the count of one
+/// letter at a time
+///
+/// Letters which none of the sets below a node have are skipped over, so a node branches on the
+/// first letter after its parent's which some set below it has.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TrieNode {
+    /// words of the set with none of the letters from this node's parent's letter on
     words: Vec<Word>,
-    children: [Option<Box<TrieNode>>; 2],
+    /// the letter this node branches on, or `MAX_LETTERS` if it has no children
+    letter: u8,
+    /// a child per count of `letter`, in order of the counts
+    children: Vec<(u8, TrieNode)>,
+}
+
+impl Default for TrieNode {
+    fn default() -> Self {
+        Self {
+            words: Vec::new(),
+            letter: MAX_LETTERS as u8,
+            children: Vec::new(),
+        }
+    }
 }
 
 impl TrieNode {
     pub fn insert(&mut self, entry: (AlphaMultiset, Vec<Word>)) {
-        self.insert_impl(0, (entry.0.into(), entry.1))
+        self.entry(0, &entry.0.char_counts()).words = entry.1;
     }
 
-    fn insert_impl(&mut self, index: usize, mut entry: (BitArray, Vec<Word>)) {
-        if entry.0.is_empty() {
-            self.words = entry.1;
-            return;
-        }
+    /// The node below this one of the multiset with `counts`, given that the letters before
+    /// `start` have been branched on already, adding it and the nodes on the way to it if needed
+    fn entry(&mut self, start: usize, counts: &[u8; MAX_LETTERS]) -> &mut TrieNode {
+        let first = match counts[start..].iter().position(|&c| c > 0) {
+            Some(i) => start + i,
+            None => return self,
+        };
 
-        let bit = entry.0.get(index).unwrap();
-        entry.0.set(index, false);
+        // branch on an earlier letter than before, with everything that was here under a count of 0
+        if first < self.letter as usize {
+            let mut old = std::mem::take(self);
+            self.words = std::mem::take(&mut old.words);
+            self.letter = first as u8;
+            if !old.children.is_empty() {
+                self.children.push((0, old));
+            }
+        }
 
-        let child = self.children[bit as usize].get_or_insert_with(Default::default);
+        let letter = self.letter as usize;
+        let count = counts[letter];
+        let i = match self.children.binary_search_by_key(&count, |&(c, _)| c) {
+            Ok(i) => i,
+            Err(i) => {
+                self.children.insert(i, (count, TrieNode::default()));
+                i
+            }
+        };
 
-        child.insert_impl(index + 1, entry);
+        return self.children[i].1.entry(letter + 1, counts);
     }
 
-    pub fn lookup(&self, set: AlphaMultiset) -> Vec<Word> {
-        self.lookup_impl(0, set.into())
-    }
+    /// Remove `word` from the node below this one of the multiset with `counts`, given that the
+    /// letters before `start` have been branched on already, dropping children left without any
+    /// words, and return whether it was there
+    fn remove(&mut self, start: usize, counts: &[u8; MAX_LETTERS], word: &str) -> bool {
+        let first = match counts[start..].iter().position(|&c| c > 0) {
+            Some(i) => start + i,
+            None => {
+                let len = self.words.len();
+                self.words.retain(|w| **w != *word);
+                return self.words.len() != len;
+            }
+        };
+        if first < self.letter as usize {
+            return false;
+        }
+
+        let letter = self.letter as usize;
+        let i = match self
+            .children
+            .binary_search_by_key(&counts[letter], |&(c, _)| c)
+        {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+        let child = &mut self.children[i].1;
+        let removed = child.remove(letter + 1, counts, word);
+        if child.words.is_empty() && child.children.is_empty() {
+            self.children.remove(i);
+        }
 
-    fn lookup_impl(&self, index: usize, mut set: BitArray) -> Vec<Word> {
-        // TODO: could we speed this up by looking at the next 2 bits instead of just 1?
-        let bit = set.get(index).unwrap();
-        set.set(index, false);
+        // a node left with only a count of 0 of its letter branches on its child's letter instead,
+        // whose words would be the same set as this node's, so there are none
+        match &mut self.children[..] {
+            [] => self.letter = MAX_LETTERS as u8,
+            [(0, child)] => {
+                let child = std::mem::take(child);
+                self.letter = child.letter;
+                self.children = child.children;
+            }
+            _ => {}
+        }
 
-        let mut words = self.words.clone();
+        return removed;
+    }
 
-        let sub_words = if bit {
-            self.children
-                .iter()
-                .filter_map(|c| c.as_ref())
-                .flat_map(|c| c.lookup_impl(index + 1, set.clone()))
-                .collect()
-        } else if let Some(child) = &self.children[0] {
-            child.lookup_impl(index + 1, set)
+    pub fn lookup_iter(&self, set: AlphaMultiset, prune: FilterBounds) -> TrieLookupIter<'_> {
+        let needed = prune.required.char_counts();
+        let stack = if prune.is_satisfiable(&set) {
+            vec![TrieFrame {
+                node: self,
+                start: 0,
+                blanks: set.blanks(),
+                needed_total: needed.iter().map(|&c| c as usize).sum(),
+                depth: 0,
+            }]
         } else {
             Vec::new()
         };
 
-        words.extend(sub_words);
+        TrieLookupIter {
+            stack,
+            counts: set.char_counts(),
+            needed,
+            max_len: prune.max_len,
+            banned: prune.banned.char_counts(),
+            words: [].iter(),
+            steps: 0,
+        }
+    }
+}
+
+/// Depth first search through the trie, keeping track of the letters that are still available
+struct TrieLookupIter<'a> {
+    /// nodes left to visit
+    stack: Vec<TrieFrame<'a>>,
+    /// the looked up letters
+    counts: [u8; MAX_LETTERS],
+    /// letters which a word must contain
+    needed: [u8; MAX_LETTERS],
+    /// the most letters a word may have
+    max_len: usize,
+    /// letters a word may not have
+    banned: [u8; MAX_LETTERS],
+    /// words of the last node visited, which are yet to be returned
+    words: std::slice::Iter<'a, Word>,
+    /// nodes visited so far
+    steps: usize,
+}
+
+/// A node left to visit, along with the state of the search on the way to it
+#[derive(Clone, Copy)]
+struct TrieFrame<'a> {
+    node: &'a TrieNode,
+    /// the first letter not yet branched on, so the counts of the letters before it are known
+    start: usize,
+    blanks: usize,
+    /// number of letters which the word must still contain
+    needed_total: usize,
+    /// letters used on the way to this node
+    depth: usize,
+}
+
+impl<'a> Iterator for TrieLookupIter<'a> {
+    type Item = WordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.words.next() {
+                return Some(word.into());
+            }
+
+            let frame = self.stack.pop()?;
+            self.steps += 1;
+            if frame.needed_total == 0 {
+                self.words = frame.node.words.iter();
+            }
+
+            let letter = frame.node.letter as usize;
+            if letter >= MAX_LETTERS {
+                continue;
+            }
+            // none of the words below have the letters skipped over to get to this letter
+            if frame.needed_total > 0 && self.needed[frame.start..letter].iter().any(|&n| n > 0) {
+                continue;
+            }
+
+            let needed = self.needed[letter] as usize;
+            let needed_total = frame.needed_total - needed;
+            for &(count, ref child) in frame.node.children.iter() {
+                let count = count as usize;
+                if count < needed {
+                    continue;
+                }
+                // the counts only go up from here, so every later child would need more
+                let missing = count.saturating_sub(self.counts[letter] as usize);
+                let depth = frame.depth + count;
+                if (count > 0 && self.banned[letter] > 0)
+                    || missing > frame.blanks
+                    || depth + needed_total > self.max_len
+                {
+                    break;
+                }
+
+                self.stack.push(TrieFrame {
+                    node: child,
+                    start: letter + 1,
+                    blanks: frame.blanks - missing,
+                    needed_total,
+                    depth,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: Vec<Word>) -> Vec<(String, usize)> {
+        let mut words: Vec<_> = words
+            .iter()
+            .map(|w| (w.to_string(), w.frequency()))
+            .collect();
+        words.sort();
+        words
+    }
+
+    /// The letter and counts every node branches on, depth first
+    fn shape(node: &TrieNode) -> Vec<(u8, Vec<u8>)> {
+        let counts = node.children.iter().map(|&(c, _)| c).collect();
+        let mut nodes = vec![(node.letter, counts)];
+        for (_, child) in node.children.iter() {
+            nodes.extend(shape(child));
+        }
+        nodes
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut trie =
+            TrieSearcher::from_wordlist(Wordlist::from_bytes(b"ace 9\ncafe 8\nface 7\n"));
+        let rebuilt = TrieSearcher::from_wordlist(Wordlist::from_bytes(b"ace 2\ncafe 8\nbee 5\n"));
+
+        trie.insert("Bee", 5);
+        trie.insert("ace", 2);
+        assert!(trie.remove("face"));
+        assert!(!trie.remove("face"));
+        assert!(!trie.remove("fac?"));
+        assert!(trie.try_insert("fac?", 1).is_err());
+
+        for letters in &["facebde", "ebe", "a?ce", ""] {
+            assert_eq!(
+                sorted(trie.lookup(letters)),
+                sorted(rebuilt.lookup(letters))
+            );
+        }
+        // and the nodes end up branching on the same letters as if the trie was built from scratch
+        assert_eq!(shape(&trie.trie_root), shape(&rebuilt.trie_root));
 
-        return words;
+        // no branches are left behind once every word is removed
+        assert!(trie.remove("bee"));
+        assert!(trie.remove("ace"));
+        assert!(trie.remove("cafe"));
+        assert!(trie.trie_root.children.is_empty());
     }
 }
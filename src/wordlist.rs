@@ -0,0 +1,322 @@
+use crate::{Alphabet, Error, NormalizationPolicy, Result, Word};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// First bytes of any gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// How the lines of a wordlist look
+///
+/// Words without a frequency get a frequency of 0, so they rank after any word with a known one.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum WordlistFormat {
+    /// Detect the format from the first non-empty line
+    Auto,
+    /// `word<whitespace>frequency` on every line
+    FrequencyList,
+    /// One word per line, without frequencies
+    Plain,
+    /// Comma separated columns with the word in the first column and its frequency in the first
+    /// numeric column after it, if any. A first line starting with a `word` column is a header.
+    Csv,
+}
+
+impl Default for WordlistFormat {
+    fn default() -> Self {
+        WordlistFormat::Auto
+    }
+}
+
+impl WordlistFormat {
+    /// Guess the format of a wordlist from one of its lines
+    fn detect(line: &str) -> Self {
+        let mut fields = line.split_ascii_whitespace();
+
+        if line.contains(',') {
+            WordlistFormat::Csv
+        } else if let (Some(_), Some(freq), None) = (fields.next(), fields.next(), fields.next()) {
+            if freq.parse::<usize>().is_ok() {
+                WordlistFormat::FrequencyList
+            } else {
+                WordlistFormat::Plain
+            }
+        } else {
+            WordlistFormat::Plain
+        }
+    }
+
+    /// Parse a line into a word, or `None` for lines without one (like a CSV header)
+    fn parse_line(self, line: &str, line_number: usize) -> Result<Option<Word>> {
+        match self {
+            WordlistFormat::Auto => Self::detect(line).parse_line(line, line_number),
+            WordlistFormat::FrequencyList => Word::try_from_freqlist_line(line).map(Some),
+            WordlistFormat::Plain => {
+                let mut fields = line.split_ascii_whitespace();
+                match (fields.next(), fields.next()) {
+                    (Some(word), None) => Ok(Some(Word::from_pair(word.to_string(), 0))),
+                    _ => Err(Error::MalformedLine(line.to_string())),
+                }
+            }
+            WordlistFormat::Csv => {
+                let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+                let word = fields.next().unwrap_or_default();
+
+                if word.is_empty() {
+                    return Err(Error::MalformedLine(line.to_string()));
+                }
+                if line_number == 1 && word.eq_ignore_ascii_case("word") {
+                    return Ok(None);
+                }
+
+                let freq = fields.find_map(|f| f.parse().ok()).unwrap_or(0);
+                Ok(Some(Word::from_pair(word.to_string(), freq)))
+            }
+        }
+    }
+}
+
+/// A wordlist to build a searcher from, which may be gzip compressed
+#[derive(Debug, Clone)]
+pub struct Wordlist<'a> {
+    source: WordlistSource<'a>,
+    format: WordlistFormat,
+    normalization: NormalizationPolicy,
+    alphabet: Alphabet,
+}
+
+#[derive(Debug, Clone)]
+enum WordlistSource<'a> {
+    Path(PathBuf),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> Wordlist<'a> {
+    /// A wordlist file, whose format is detected unless given with `with_format`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            source: WordlistSource::Path(path.as_ref().to_path_buf()),
+            format: WordlistFormat::Auto,
+            normalization: NormalizationPolicy::default(),
+            alphabet: Alphabet::default(),
+        }
+    }
+
+    /// The contents of a wordlist file, whose format is detected unless given with `with_format`
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self {
+            source: WordlistSource::Bytes(bytes),
+            format: WordlistFormat::Auto,
+            normalization: NormalizationPolicy::default(),
+            alphabet: Alphabet::default(),
+        }
+    }
+
+    /// The frequency list embedded at compile time
+    pub fn embedded() -> Wordlist<'static> {
+        Wordlist::from_bytes(crate::word_searcher::EMBEDDED_WORDLIST)
+            .with_format(WordlistFormat::FrequencyList)
+    }
+
+    pub fn with_format(mut self, format: WordlistFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Normalize words with `policy` instead of `NormalizationPolicy::default()`
+    pub fn with_normalization(mut self, policy: NormalizationPolicy) -> Self {
+        self.normalization = policy;
+        self
+    }
+
+    pub fn normalization(&self) -> &NormalizationPolicy {
+        &self.normalization
+    }
+
+    /// Read words in `alphabet` instead of English, which the searchers built from this wordlist
+    /// then look up letters in
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// CRC32 of the wordlist as it's stored (so before any decompression), which lookup indices
+    /// built from it are stamped with
+    pub fn checksum(&self) -> Result<u32> {
+        match &self.source {
+            WordlistSource::Path(path) => {
+                let bytes = std::fs::read(path).map_err(|source| Error::Open {
+                    path: path.clone(),
+                    source,
+                })?;
+                Ok(crc32fast::hash(&bytes))
+            }
+            WordlistSource::Bytes(bytes) => Ok(crc32fast::hash(bytes)),
+        }
+    }
+
+    /// Read the words of this wordlist, reporting the line number of malformed lines
+    pub fn into_words(self) -> Result<Box<dyn Iterator<Item = Result<Word>> + 'a>> {
+        Ok(Box::new(
+            self.into_numbered_words()?.map(|w| w.map(|(_, word)| word)),
+        ))
+    }
+
+    /// Like `into_words`, along with the (1-based) line number of each word
+    pub(crate) fn into_numbered_words(
+        self,
+    ) -> Result<Box<dyn Iterator<Item = Result<(usize, Word)>> + 'a>> {
+        let reader: Box<dyn BufRead + 'a> = match self.source {
+            WordlistSource::Path(path) => {
+                let f = std::fs::File::open(&path).map_err(|source| Error::Open {
+                    path: path.clone(),
+                    source,
+                })?;
+                decompress(BufReader::new(f))?
+            }
+            WordlistSource::Bytes(bytes) => decompress(bytes)?,
+        };
+
+        let mut lines = reader.lines().enumerate().map(|(i, line)| {
+            let line = line.map_err(|e| Error::from(e).at_line(i + 1))?;
+            Ok((i + 1, line))
+        });
+
+        // detect the format once from the first non-empty line, so a plain word which happens to
+        // look like something else later on doesn't change it
+        let mut skipped = Vec::new();
+        let mut format = self.format;
+        if format == WordlistFormat::Auto {
+            for line in &mut lines {
+                let detected = match &line {
+                    Ok((_, l)) if l.trim().is_empty() => None,
+                    Ok((_, l)) => Some(WordlistFormat::detect(l)),
+                    Err(_) => Some(WordlistFormat::Auto),
+                };
+                skipped.push(line);
+
+                if let Some(detected) = detected {
+                    format = detected;
+                    break;
+                }
+            }
+        }
+
+        Ok(Box::new(skipped.into_iter().chain(lines).filter_map(
+            move |line| match line {
+                Ok((_, line)) if line.trim().is_empty() => None,
+                Ok((n, line)) => match format.parse_line(&line, n) {
+                    Ok(word) => word.map(|w| Ok((n, w))),
+                    Err(e) => Some(Err(e.at_line(n))),
+                },
+                Err(e) => Some(Err(e)),
+            },
+        )))
+    }
+}
+
+/// Transparently decompress gzip streams, detected by their magic bytes
+fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> Result<Box<dyn BufRead + 'a>> {
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+impl<'a> std::convert::From<&'a Path> for Wordlist<'a> {
+    fn from(path: &'a Path) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl<'a> std::convert::From<&'a PathBuf> for Wordlist<'a> {
+    fn from(path: &'a PathBuf) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl std::convert::From<PathBuf> for Wordlist<'_> {
+    fn from(path: PathBuf) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl<'a> std::convert::From<&'a str> for Wordlist<'a> {
+    fn from(path: &'a str) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl<'a> std::convert::From<&'a String> for Wordlist<'a> {
+    fn from(path: &'a String) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl std::convert::From<String> for Wordlist<'_> {
+    fn from(path: String) -> Self {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8], format: WordlistFormat) -> Result<Vec<Word>> {
+        Wordlist::from_bytes(bytes)
+            .with_format(format)
+            .into_words()?
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_formats() {
+        let pairs = |words: Vec<Word>| {
+            words
+                .iter()
+                .map(|w| (w.to_string(), w.frequency()))
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![("apple".to_string(), 12), ("pear".to_string(), 3)];
+
+        let freqlist = read(b"apple 12\n\npear 3\n", WordlistFormat::Auto).unwrap();
+        assert_eq!(pairs(freqlist), expected);
+
+        let csv = read(
+            b"word,pos,freq\napple,noun,12\n\"pear\",noun,3\n",
+            WordlistFormat::Auto,
+        );
+        assert_eq!(pairs(csv.unwrap()), expected);
+
+        let plain = read(b"\napple\npear\n", WordlistFormat::Auto).unwrap();
+        assert_eq!(plain[1].to_string(), "pear");
+        assert_eq!(plain[1].frequency(), 0);
+    }
+
+    #[test]
+    fn test_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(b"apple 12\npear 3\n").unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        assert_eq!(read(&bytes, WordlistFormat::Auto).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        let err = read(b"apple 12\npear\n", WordlistFormat::FrequencyList).unwrap_err();
+        assert!(matches!(err, Error::Wordlist { line: 2, .. }));
+
+        let err = read(b"apple\ngreen pear\n", WordlistFormat::Auto).unwrap_err();
+        assert!(matches!(err, Error::Wordlist { line: 2, .. }));
+    }
+}
@@ -0,0 +1,341 @@
+//! Every searcher has to find exactly the words a brute force search through the wordlist finds,
+//! for any letters, filter and lookup options
+
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+use wordscapes_helper::*;
+
+/// Number of the most frequent words of the embedded wordlist to build the searchers from, which
+/// share enough letters to find plenty of words
+const WORDS: usize = 1000;
+
+fn wordlist_bytes() -> &'static [u8] {
+    let bytes: &[u8] = include_bytes!("../src/freq_200k.txt");
+    let end = bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, &b)| b == b'\n')
+        .nth(WORDS - 1)
+        .map(|(i, _)| i + 1)
+        .unwrap_or_else(|| bytes.len());
+
+    &bytes[..end]
+}
+
+fn wordlist() -> Wordlist<'static> {
+    Wordlist::from_bytes(wordlist_bytes())
+}
+
+/// Counts of each of the letters `a` to `z`
+type Counts = [usize; 26];
+
+/// The words of the wordlist as the searchers store them, along with their letter counts
+///
+/// This follows the default normalization (lowercase, drop anything but English letters, skip
+/// words shorter than 3 letters) without going through the library, so a bug there can't be shared.
+fn reference_words() -> Vec<(Counts, Word)> {
+    let text = std::str::from_utf8(wordlist_bytes()).unwrap();
+
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let word: String = parts
+                .next()?
+                .chars()
+                .filter(char::is_ascii_alphabetic)
+                .map(|c| c.to_ascii_lowercase())
+                .collect();
+            let frequency = parts.next().map_or(0, |f| f.parse().unwrap());
+
+            if word.len() < 3 {
+                return None;
+            }
+            let (counts, _) = count_letters(&word);
+            Some((counts, Word::from_pair(word, frequency)))
+        })
+        .collect()
+}
+
+/// The letter counts of `letters` and how many blanks (`?` or `*`) it has
+fn count_letters(letters: &str) -> (Counts, usize) {
+    let mut counts = [0; 26];
+    let mut blanks = 0;
+
+    for b in letters.bytes() {
+        match b {
+            b'a'..=b'z' => counts[(b - b'a') as usize] += 1,
+            b'?' | b'*' => blanks += 1,
+            _ => panic!("unexpected letter {:?} in {:?}", b as char, letters),
+        }
+    }
+
+    (counts, blanks)
+}
+
+/// Every word which can be made from `letters`, matches `filter` and meets `options`
+fn brute_force(
+    words: &[(Counts, Word)],
+    letters: &str,
+    filter: &Filter,
+    options: &LookupOptions,
+) -> Vec<Word> {
+    let (letters, blanks) = count_letters(letters);
+    let (required, _) = count_letters(&options.required.iter().collect::<String>());
+    let (banned, _) = count_letters(&options.banned.iter().collect::<String>());
+
+    let mut found: Vec<_> = words
+        .iter()
+        .filter(|(counts, _)| {
+            let missing: usize = counts
+                .iter()
+                .zip(letters.iter())
+                .map(|(&need, &have)| need.saturating_sub(have))
+                .sum();
+            missing <= blanks
+        })
+        .filter(|(counts, _)| {
+            counts.iter().zip(required.iter().zip(banned.iter())).all(
+                |(&count, (&required, &banned))| count >= required && (banned == 0 || count == 0),
+            )
+        })
+        .filter(|(_, w)| filter.matches(w))
+        .map(|(_, w)| w.clone())
+        .collect();
+    found.sort();
+    found
+}
+
+fn searchers() -> Vec<(&'static str, Box<dyn WordSearcher>)> {
+    let dag = DAGSearcher::from_wordlist(wordlist());
+    let index = DAGIndex::try_from_bytes(dag.to_index_bytes()).unwrap();
+    // whichever searchers calibration picks have to find the same words
+    let mut calibrated = HybridSearcher::from_wordlist(wordlist());
+    calibrated.calibrate();
+
+    vec![
+        (
+            "automaton",
+            Box::new(AutomatonSearcher::from_wordlist(wordlist())),
+        ),
+        (
+            "columnar",
+            Box::new(ColumnarSearcher::from_wordlist(wordlist())),
+        ),
+        ("dag", Box::new(dag)),
+        ("dag index", Box::new(index)),
+        ("exp", Box::new(ExpSearcher::from_wordlist(wordlist()))),
+        ("fst", Box::new(FstSearcher::from_wordlist(wordlist()))),
+        (
+            "hybrid",
+            Box::new(HybridSearcher::from_wordlist(wordlist())),
+        ),
+        ("calibrated hybrid", Box::new(calibrated)),
+        (
+            "simple",
+            Box::new(SimpleSearcher::from_wordlist(wordlist())),
+        ),
+        ("trie", Box::new(TrieSearcher::from_wordlist(wordlist()))),
+    ]
+}
+
+/// Looked up letters, either any letters, common ones or long lookups, with a few blanks at most
+fn letters() -> impl Strategy<Value = String> {
+    let letters = prop_oneof!["[a-z]{0,10}", "[aeilnorst]{2,10}", "[a-z]{15,24}"];
+
+    (letters, "[?]{0,3}").prop_map(|(letters, blanks)| letters + &blanks)
+}
+
+/// Filters of every kind, from shorthand filters to patterns
+fn filter() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::new()),
+        "[3-7]",
+        "[a-z_]{2,7}",
+        "[a-z]{0,2}[2-4][a-z]?",
+        "/[a-z.]{2,6}",
+        r"/\^[a-z]{1,2}",
+        r"/[a-z]{1,2}\$",
+        r"/[a-z]?\[[aeiou]{1,3}\][a-z]?\.\*",
+        r"/[a-z]{1,2}\(s\|ed\|ing\)\?",
+    ]
+}
+
+/// Required and banned letters, which may well contradict each other
+fn options() -> impl Strategy<Value = (String, String)> {
+    ("[a-z]{0,2}", "[a-z]{0,2}")
+}
+
+#[test]
+fn test_searchers_match_brute_force() {
+    let words = reference_words();
+    let searchers = searchers();
+    let mut runner = TestRunner::new(Config {
+        cases: 256,
+        ..Config::default()
+    });
+
+    runner
+        .run(
+            &(letters(), filter(), options()),
+            |(letters, filter, (required, banned))| {
+                let filter = if filter.is_empty() {
+                    Filter::Permissive
+                } else {
+                    Filter::new(&filter)
+                };
+                let options = LookupOptions::new(&required, &banned);
+                let expected = brute_force(&words, &letters, &filter, &options);
+
+                for (name, searcher) in searchers.iter() {
+                    let mut found: Vec<_> = searcher
+                        .lookup_iter_with(&letters, &filter, &options)
+                        .map(Word::from)
+                        .collect();
+                    found.sort();
+                    prop_assert_eq!(&found, &expected, "{} searcher", name);
+                }
+
+                Ok(())
+            },
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_searchers_match_without_filter() {
+    let words = reference_words();
+    let searchers = searchers();
+    let mut runner = TestRunner::new(Config {
+        cases: 256,
+        ..Config::default()
+    });
+
+    runner
+        .run(&letters(), |letters| {
+            let expected = brute_force(
+                &words,
+                &letters,
+                &Filter::Permissive,
+                &LookupOptions::default(),
+            );
+
+            for (name, searcher) in searchers.iter() {
+                let mut found = searcher.lookup(&letters);
+                found.sort();
+                prop_assert_eq!(&found, &expected, "{} searcher", name);
+            }
+
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_searchers_match_with_blanks() {
+    let words = reference_words();
+    let searchers = searchers();
+    let mut runner = TestRunner::new(Config {
+        cases: 64,
+        ..Config::default()
+    });
+
+    runner
+        .run(&("[a-z]{0,8}", "[?]{2,3}"), |(letters, blanks)| {
+            let letters = letters + &blanks;
+            let expected = brute_force(
+                &words,
+                &letters,
+                &Filter::Permissive,
+                &LookupOptions::default(),
+            );
+
+            for (name, searcher) in searchers.iter() {
+                let mut found = searcher.lookup(&letters);
+                found.sort();
+                prop_assert_eq!(&found, &expected, "{} searcher", name);
+            }
+
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_lookup_stats_match_lookup() {
+    let searchers = searchers();
+    let mut runner = TestRunner::new(Config {
+        cases: 64,
+        ..Config::default()
+    });
+
+    runner
+        .run(&letters(), |letters| {
+            for (name, searcher) in searchers.iter() {
+                let (found, stats) = searcher.lookup_stats(&letters);
+                prop_assert_eq!(&found, &searcher.lookup(&letters), "{} searcher", name);
+                prop_assert_eq!(stats.found, found.len(), "{} searcher", name);
+                prop_assert!(
+                    stats.steps.unwrap_or(0) > 0 || found.is_empty(),
+                    "{} searcher",
+                    name
+                );
+                prop_assert!(stats.elapsed.is_some(), "{} searcher", name);
+            }
+
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_searchers_of_empty_wordlist() {
+    let empty = || Wordlist::from_bytes(b"");
+    let dag = DAGSearcher::try_from_wordlist(empty()).unwrap();
+    let searchers: Vec<(&str, Box<dyn WordSearcher>)> = vec![
+        (
+            "automaton",
+            Box::new(AutomatonSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+        (
+            "columnar",
+            Box::new(ColumnarSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+        (
+            "dag index",
+            Box::new(DAGIndex::try_from_bytes(dag.to_index_bytes()).unwrap()),
+        ),
+        ("dag", Box::new(dag)),
+        (
+            "exp",
+            Box::new(ExpSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+        (
+            "fst",
+            Box::new(FstSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+        (
+            "hybrid",
+            Box::new(HybridSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+        (
+            "simple",
+            Box::new(SimpleSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+        (
+            "trie",
+            Box::new(TrieSearcher::try_from_wordlist(empty()).unwrap()),
+        ),
+    ];
+
+    for (name, searcher) in searchers.iter() {
+        for letters in &["", "abc", "ab?", "abcdefghijklmnopqrstuvwxyz"] {
+            assert_eq!(searcher.lookup(letters), vec![], "{} searcher", name);
+            assert_eq!(
+                searcher.lookup_top_k(letters, 3, Ranking::Frequency),
+                vec![],
+                "{} searcher",
+                name
+            );
+        }
+    }
+}